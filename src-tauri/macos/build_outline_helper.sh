@@ -30,8 +30,13 @@ mkdir -p "$APP_DIR/Sources"
 cat > "$APP_DIR/Sources/main.swift" <<'SWIFT'
 import AppKit
 
+// Протокол с приложением (JSON-строки):
+//   stdout: {"type":"ready"} после показа окна, {"type":"ack","id":N} после применения стиля
+//   stdin:  {"id":N,"color":"#RRGGBB","width":4,"blur":0,"animation":"none|pulse|flash"}; EOF — завершение
 final class OutlineWindow: NSWindow {
-    init(frame: NSRect, color: NSColor) {
+    private let outline = CAShapeLayer()
+
+    init(frame: NSRect) {
         super.init(
             contentRect: frame,
             styleMask: [.borderless],
@@ -44,16 +49,46 @@ final class OutlineWindow: NSWindow {
         self.ignoresMouseEvents = true
         self.collectionBehavior = [.canJoinAllSpaces, .fullScreenAuxiliary, .transient]
 
-        let outline = CAShapeLayer()
         outline.fillColor = NSColor.clear.cgColor
-        outline.strokeColor = color.cgColor
-        outline.lineWidth = 4.0
-        let path = CGMutablePath()
-        path.addRect(self.contentView!.bounds)
-        outline.path = path
         self.contentView!.wantsLayer = true
         self.contentView!.layer?.addSublayer(outline)
     }
+
+    func apply(color: NSColor, width: CGFloat, blur: CGFloat, animation: String) {
+        let bounds = self.contentView!.bounds
+        outline.frame = bounds
+        outline.path = CGPath(rect: bounds.insetBy(dx: width / 2, dy: width / 2), transform: nil)
+        outline.strokeColor = color.cgColor
+        outline.lineWidth = width
+        outline.shadowColor = color.cgColor
+        outline.shadowRadius = blur
+        outline.shadowOpacity = blur > 0 ? 1.0 : 0.0
+        outline.shadowOffset = .zero
+        animate(animation)
+    }
+
+    private func animate(_ animation: String) {
+        outline.removeAnimation(forKey: "outline")
+        let fade = CABasicAnimation(keyPath: "opacity")
+        switch animation {
+        case "pulse":
+            fade.fromValue = 1.0
+            fade.toValue = 0.35
+            fade.duration = 0.8
+            fade.autoreverses = true
+            fade.repeatCount = .infinity
+            fade.timingFunction = CAMediaTimingFunction(name: .easeInEaseOut)
+        case "flash":
+            fade.fromValue = 1.0
+            fade.toValue = 0.0
+            fade.duration = 0.15
+            fade.autoreverses = true
+            fade.repeatCount = 4
+        default:
+            return
+        }
+        outline.add(fade, forKey: "outline")
+    }
 }
 
 func parseColor(_ hex: String) -> NSColor {
@@ -69,18 +104,52 @@ func parseColor(_ hex: String) -> NSColor {
     return NSColor(calibratedRed: r, green: g, blue: b, alpha: 1.0)
 }
 
-let args = CommandLine.arguments
-var colorHex = "FF4D4F"
-if let idx = args.firstIndex(of: "--color"), args.count > idx + 1 {
-    colorHex = args[idx + 1]
+func send(_ message: [String: Any]) {
+    guard let data = try? JSONSerialization.data(withJSONObject: message),
+          let line = String(data: data, encoding: .utf8) else { return }
+    FileHandle.standardOutput.write((line + "\n").data(using: .utf8)!)
+}
+
+func argument(_ name: String) -> String? {
+    let args = CommandLine.arguments
+    guard let idx = args.firstIndex(of: name), args.count > idx + 1 else { return nil }
+    return args[idx + 1]
 }
 
+var color = parseColor(argument("--color") ?? "FF4D4F")
+var width = CGFloat(Double(argument("--width") ?? "") ?? 4.0)
+var blur = CGFloat(Double(argument("--blur") ?? "") ?? 0.0)
+var animation = argument("--animation") ?? "none"
+
 let app = NSApplication.shared
 app.setActivationPolicy(.accessory)
 
 let screenFrame = NSScreen.main?.frame ?? NSRect(x: 0, y: 0, width: 1280, height: 800)
-let window = OutlineWindow(frame: screenFrame, color: parseColor(colorHex))
+let window = OutlineWindow(frame: screenFrame)
+window.apply(color: color, width: width, blur: blur, animation: animation)
 window.makeKeyAndOrderFront(nil)
+send(["type": "ready"])
+
+// stdin читается в фоне, стиль применяется на главном потоке
+Thread.detachNewThread {
+    while let line = readLine() {
+        guard let data = line.data(using: .utf8),
+              let message = try? JSONSerialization.jsonObject(with: data) as? [String: Any] else {
+            send(["type": "error", "message": "invalid message"])
+            continue
+        }
+        DispatchQueue.main.async {
+            if let hex = message["color"] as? String { color = parseColor(hex) }
+            if let w = message["width"] as? Double { width = CGFloat(w) }
+            if let b = message["blur"] as? Double { blur = CGFloat(b) }
+            if let a = message["animation"] as? String { animation = a }
+            window.apply(color: color, width: width, blur: blur, animation: animation)
+            if let id = message["id"] as? Int { send(["type": "ack", "id": id]) }
+        }
+    }
+    // Приложение закрыло stdin или завершилось само
+    exit(0)
+}
 
 RunLoop.main.run()
 SWIFT
@@ -0,0 +1,362 @@
+// Журнал удалённых действий: append-only JSONL, каждая запись ссылается на хеш предыдущей
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// prev_hash первой записи
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+/// Поля параметров, значения которых в журнал не попадают
+const REDACTED_KEYS: &[&str] = &["text", "password", "token", "secret", "upload_url"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditKind {
+    TaskReceived,
+    Action,
+    TaskFinished,
+    /// Запись продолжена после нечитаемых строк; цепочка идёт от последней целой записи
+    SegmentStarted,
+}
+
+/// Содержимое записи; хеш считается от этой части в сериализованном виде
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub seq: u64,
+    pub timestamp: String,
+    pub kind: AuditKind,
+    pub task_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub params: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+}
+
+impl AuditRecord {
+    pub fn new(kind: AuditKind, task_id: &str) -> Self {
+        Self {
+            seq: 0,
+            timestamp: String::new(),
+            kind,
+            task_id: task_id.to_string(),
+            action_id: None,
+            action_type: None,
+            params: None,
+            status: None,
+            error: None,
+            duration_ms: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEvent {
+    #[serde(flatten)]
+    pub record: AuditRecord,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditVerification {
+    pub valid: bool,
+    pub events: u64,
+    /// seq первой записи, на которой цепочка не сходится; для нечитаемой строки — seq, который там ожидался
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub broken_at: Option<u64>,
+}
+
+struct Tail {
+    seq: u64,
+    hash: String,
+}
+
+pub struct AuditLog {
+    path: PathBuf,
+    tail: Mutex<Option<Tail>>,
+}
+
+impl AuditLog {
+    /// Файл открывается при первой записи, чтобы недоступный каталог не мешал запуску приложения
+    pub fn new(path: PathBuf) -> Self {
+        Self { path, tail: Mutex::new(None) }
+    }
+
+    /// Ошибки записи только логируются: журнал не должен останавливать выполнение задачи
+    pub fn append(&self, record: AuditRecord) {
+        if let Err(e) = self.try_append(record) {
+            log::error!("failed to write audit log {}: {}", self.path.display(), e);
+        }
+    }
+
+    fn try_append(&self, record: AuditRecord) -> io::Result<()> {
+        let mut tail = self.tail.lock().unwrap();
+        if tail.is_none() {
+            let (last, skipped) = read_tail(&self.path)?;
+            *tail = Some(last);
+            // Одна испорченная строка (например, оборванная при падении) не должна останавливать журнал:
+            // начинаем новый сегмент от последней целой записи, а verify по-прежнему покажет разрыв
+            if skipped > 0 {
+                log::warn!("audit log {} has {} unreadable lines, starting a new segment", self.path.display(), skipped);
+                let marker = AuditRecord {
+                    error: Some(format!("{} unreadable lines skipped", skipped)),
+                    ..AuditRecord::new(AuditKind::SegmentStarted, "")
+                };
+                self.write(tail.as_mut().expect("tail was just loaded"), marker)?;
+            }
+        }
+        self.write(tail.as_mut().expect("tail was just loaded"), record)
+    }
+
+    fn write(&self, last: &mut Tail, mut record: AuditRecord) -> io::Result<()> {
+        record.seq = last.seq + 1;
+        record.timestamp = chrono::Utc::now().to_rfc3339();
+        let event = AuditEvent { hash: chain_hash(&last.hash, &record)?, prev_hash: last.hash.clone(), record };
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        let mut line = serde_json::to_vec(&event)?;
+        line.push(b'\n');
+        file.write_all(&line)?;
+        file.flush()?;
+
+        last.seq = event.record.seq;
+        last.hash = event.hash;
+        Ok(())
+    }
+
+    /// События не раньше `since` (RFC 3339), последние `limit` штук в хронологическом порядке
+    pub fn list(&self, since: Option<&str>, limit: usize) -> io::Result<Vec<AuditEvent>> {
+        let since = since
+            .map(chrono::DateTime::parse_from_rfc3339)
+            .transpose()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let mut events: Vec<AuditEvent> = read_events(&self.path)?
+            .into_iter()
+            .flatten()
+            .filter(|e| match since {
+                Some(since) => chrono::DateTime::parse_from_rfc3339(&e.record.timestamp).is_ok_and(|t| t >= since),
+                None => true,
+            })
+            .collect();
+        if events.len() > limit {
+            events.drain(..events.len() - limit);
+        }
+        Ok(events)
+    }
+
+    /// Копия журнала в JSONL; возвращает число записей
+    pub fn export(&self, destination: &Path) -> io::Result<usize> {
+        let _tail = self.tail.lock().unwrap();
+        if !self.path.exists() {
+            File::create(destination)?;
+            return Ok(0);
+        }
+        fs::copy(&self.path, destination)?;
+        Ok(read_events(destination)?.iter().flatten().count())
+    }
+
+    pub fn verify(&self) -> io::Result<AuditVerification> {
+        let mut prev = GENESIS_HASH.to_string();
+        let (mut count, mut seq) = (0, 0);
+        for line in read_events(&self.path)? {
+            let Some(event) = line else {
+                return Ok(AuditVerification { valid: false, events: count, broken_at: Some(seq + 1) });
+            };
+            count += 1;
+            if event.prev_hash != prev || chain_hash(&prev, &event.record)? != event.hash {
+                return Ok(AuditVerification { valid: false, events: count, broken_at: Some(event.record.seq) });
+            }
+            seq = event.record.seq;
+            prev = event.hash;
+        }
+        Ok(AuditVerification { valid: true, events: count, broken_at: None })
+    }
+}
+
+fn chain_hash(prev_hash: &str, record: &AuditRecord) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    hasher.update(prev_hash.as_bytes());
+    hasher.update(serde_json::to_vec(record)?);
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Все строки журнала; нечитаемая строка не прерывает чтение и даёт None
+fn read_events(path: &Path) -> io::Result<Vec<Option<AuditEvent>>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut events = Vec::new();
+    // Байты читаются без проверки UTF-8: испорченная кодировка — такая же нечитаемая строка
+    for line in BufReader::new(file).split(b'\n') {
+        let line = line?;
+        if line.iter().all(u8::is_ascii_whitespace) {
+            continue;
+        }
+        events.push(serde_json::from_slice(&line).ok());
+    }
+    Ok(events)
+}
+
+/// Последняя целая запись и число нечитаемых строк после неё
+fn read_tail(path: &Path) -> io::Result<(Tail, usize)> {
+    let mut lines = read_events(path)?;
+    let mut skipped = 0;
+    while let Some(line) = lines.pop() {
+        match line {
+            Some(last) => return Ok((Tail { seq: last.record.seq, hash: last.hash }, skipped)),
+            None => skipped += 1,
+        }
+    }
+    Ok((Tail { seq: 0, hash: GENESIS_HASH.to_string() }, skipped))
+}
+
+/// Параметры для журнала: набранный текст и секреты заменяются длиной
+pub fn redact(params: &serde_json::Value) -> serde_json::Value {
+    match params {
+        serde_json::Value::Object(map) => map
+            .iter()
+            .map(|(key, value)| {
+                let value = match value {
+                    serde_json::Value::String(s) if REDACTED_KEYS.contains(&key.as_str()) => {
+                        serde_json::Value::String(format!("[redacted {} chars]", s.chars().count()))
+                    }
+                    other => redact(other),
+                };
+                (key.clone(), value)
+            })
+            .collect(),
+        serde_json::Value::Array(items) => items.iter().map(redact).collect(),
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_log() -> AuditLog {
+        AuditLog::new(std::env::temp_dir().join(format!("skygen-audit-{}", uuid::Uuid::new_v4())).join("audit.jsonl"))
+    }
+
+    fn cleanup(log: &AuditLog) {
+        let _ = fs::remove_dir_all(log.path.parent().unwrap());
+    }
+
+    fn write_task(log: &AuditLog, task_id: &str) {
+        log.append(AuditRecord::new(AuditKind::TaskReceived, task_id));
+        log.append(AuditRecord { action_type: Some("click".into()), ..AuditRecord::new(AuditKind::Action, task_id) });
+        log.append(AuditRecord { status: Some("completed".into()), ..AuditRecord::new(AuditKind::TaskFinished, task_id) });
+    }
+
+    #[test]
+    fn appended_events_form_a_valid_chain() {
+        let log = temp_log();
+        write_task(&log, "t1");
+
+        let events = log.list(None, 100).unwrap();
+        assert_eq!(events.iter().map(|e| e.record.seq).collect::<Vec<_>>(), [1, 2, 3]);
+        assert_eq!(events[0].prev_hash, GENESIS_HASH);
+        assert_eq!(events[1].prev_hash, events[0].hash);
+
+        let result = log.verify().unwrap();
+        assert!(result.valid);
+        assert_eq!(result.events, 3);
+        assert_eq!(result.broken_at, None);
+        cleanup(&log);
+    }
+
+    #[test]
+    fn chain_continues_after_reopen() {
+        let log = temp_log();
+        write_task(&log, "t1");
+        let reopened = AuditLog::new(log.path.clone());
+        write_task(&reopened, "t2");
+
+        assert_eq!(reopened.list(None, 100).unwrap().last().unwrap().record.seq, 6);
+        assert!(reopened.verify().unwrap().valid);
+        cleanup(&log);
+    }
+
+    #[test]
+    fn edited_record_breaks_the_chain() {
+        let log = temp_log();
+        write_task(&log, "t1");
+        let content = fs::read_to_string(&log.path).unwrap();
+        fs::write(&log.path, content.replacen("\"click\"", "\"scroll\"", 1)).unwrap();
+
+        let result = log.verify().unwrap();
+        assert!(!result.valid);
+        assert_eq!(result.broken_at, Some(2));
+        cleanup(&log);
+    }
+
+    #[test]
+    fn removed_record_breaks_the_chain() {
+        let log = temp_log();
+        write_task(&log, "t1");
+        let content = fs::read_to_string(&log.path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        fs::write(&log.path, format!("{}\n{}\n", lines[0], lines[2])).unwrap();
+
+        assert_eq!(log.verify().unwrap().broken_at, Some(3));
+        cleanup(&log);
+    }
+
+    #[test]
+    fn unreadable_line_is_reported_and_logging_continues() {
+        let log = temp_log();
+        write_task(&log, "t1");
+        let mut file = OpenOptions::new().append(true).open(&log.path).unwrap();
+        file.write_all(b"{\"seq\":4,\"kind\":\"act\n").unwrap();
+
+        let fresh = AuditLog::new(log.path.clone());
+        let result = fresh.verify().unwrap();
+        assert!(!result.valid);
+        assert_eq!(result.events, 3);
+        assert_eq!(result.broken_at, Some(4));
+
+        fresh.append(AuditRecord::new(AuditKind::TaskReceived, "t2"));
+        let events = fresh.list(None, 100).unwrap();
+        assert_eq!(events.len(), 5);
+        assert_eq!(events[3].record.kind, AuditKind::SegmentStarted);
+        assert_eq!(events[3].record.seq, 4);
+        assert_eq!(events[3].prev_hash, events[2].hash);
+        assert_eq!(events[4].record.task_id, "t2");
+        assert_eq!(events[4].prev_hash, events[3].hash);
+        cleanup(&log);
+    }
+
+    #[test]
+    fn secrets_and_typed_text_are_redacted() {
+        let params = serde_json::json!({
+            "text": "hunter2",
+            "x": 10,
+            "nested": { "token": "abc", "button": "left" },
+            "items": [{ "password": "пароль" }],
+        });
+        assert_eq!(
+            redact(&params),
+            serde_json::json!({
+                "text": "[redacted 7 chars]",
+                "x": 10,
+                "nested": { "token": "[redacted 3 chars]", "button": "left" },
+                "items": [{ "password": "[redacted 6 chars]" }],
+            })
+        );
+    }
+}
@@ -0,0 +1,97 @@
+// Экспоненциальная задержка переподключения с джиттером
+use rand::Rng;
+use std::time::Duration;
+
+pub struct Backoff {
+    base: Duration,
+    max: Duration,
+    attempt: u32,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(1), Duration::from_secs(60))
+    }
+}
+
+impl Backoff {
+    pub fn new(base: Duration, max: Duration) -> Self {
+        Self { base, max, attempt: 0 }
+    }
+
+    pub fn attempt(&self) -> u32 {
+        self.attempt
+    }
+
+    pub fn reset(&mut self) {
+        self.attempt = 0;
+    }
+
+    /// Следующая задержка: base * 2^attempt, не больше max, со случайным разбросом в [d/2, d],
+    /// чтобы устройства после общего сбоя сети не переподключались одновременно
+    pub fn next_delay(&mut self) -> Duration {
+        let capped = self.ceiling();
+        self.attempt = self.attempt.saturating_add(1);
+        let half = capped / 2;
+        let jitter_ms = rand::thread_rng().gen_range(0..=half.as_millis() as u64);
+        half + Duration::from_millis(jitter_ms)
+    }
+
+    /// Верхняя граница следующей задержки, без джиттера
+    fn ceiling(&self) -> Duration {
+        self.base.saturating_mul(2u32.saturating_pow(self.attempt.min(16))).min(self.max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(values: &[u64]) -> Vec<Duration> {
+        values.iter().map(|s| Duration::from_secs(*s)).collect()
+    }
+
+    #[test]
+    fn ceiling_doubles_up_to_max() {
+        let mut backoff = Backoff::default();
+        let ceilings: Vec<Duration> = (0..9)
+            .map(|_| {
+                let ceiling = backoff.ceiling();
+                backoff.next_delay();
+                ceiling
+            })
+            .collect();
+        assert_eq!(ceilings, secs(&[1, 2, 4, 8, 16, 32, 60, 60, 60]));
+        assert_eq!(backoff.attempt(), 9);
+    }
+
+    #[test]
+    fn delay_stays_within_jitter_window() {
+        let mut backoff = Backoff::new(Duration::from_millis(100), Duration::from_secs(2));
+        for _ in 0..50 {
+            let ceiling = backoff.ceiling();
+            let delay = backoff.next_delay();
+            assert!(delay >= ceiling / 2 && delay <= ceiling, "{:?} outside [{:?}, {:?}]", delay, ceiling / 2, ceiling);
+        }
+    }
+
+    #[test]
+    fn huge_attempt_count_does_not_overflow() {
+        let mut backoff = Backoff::new(Duration::from_secs(1), Duration::from_secs(60));
+        backoff.attempt = u32::MAX;
+        assert_eq!(backoff.ceiling(), Duration::from_secs(60));
+        backoff.next_delay();
+        assert_eq!(backoff.attempt(), u32::MAX);
+    }
+
+    #[test]
+    fn reset_starts_from_base() {
+        let mut backoff = Backoff::default();
+        for _ in 0..5 {
+            backoff.next_delay();
+        }
+        backoff.reset();
+        assert_eq!(backoff.attempt(), 0);
+        assert!(backoff.next_delay() <= Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,493 @@
+// Драйвер WebSocket-соединения устройства: handshake, heartbeat и очередь задач
+use futures_util::future::BoxFuture;
+use futures_util::{Sink, SinkExt, Stream, StreamExt};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::{self, Message};
+use tokio_util::sync::CancellationToken;
+
+use super::protocol::{ActionResult, ClientMessage, ServerMessage, TaskExec, TaskStatus};
+use crate::skygen_client::DeviceCredentials;
+
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+const REGISTER_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Исполнитель задач task.exec. Задачи выполняются по одной, в порядке поступления.
+/// После отмены `cancel` оставшиеся действия не выполняются и получают статус cancelled.
+pub trait TaskRunner: Send + Sync {
+    fn run<'a>(&'a self, task: &'a TaskExec, cancel: &'a CancellationToken) -> BoxFuture<'a, Vec<ActionResult>>;
+}
+
+/// Аварийная остановка: отменяет задачу в работе и всё, что успело встать в очередь.
+/// Переживает переподключения, поэтому принадлежит агенту, а не сессии.
+#[derive(Default)]
+pub struct KillSwitch {
+    token: Mutex<CancellationToken>,
+}
+
+impl KillSwitch {
+    pub fn trigger(&self) {
+        self.token.lock().unwrap().cancel();
+    }
+
+    fn token(&self) -> CancellationToken {
+        self.token.lock().unwrap().clone()
+    }
+
+    fn reset(&self) {
+        let mut token = self.token.lock().unwrap();
+        if token.is_cancelled() {
+            *token = CancellationToken::new();
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum DriverEvent {
+    Registered,
+    TaskStarted { task_id: String },
+    TaskFinished { task_id: String, status: TaskStatus },
+}
+
+pub type EventSink = Arc<dyn Fn(DriverEvent) + Send + Sync>;
+
+#[derive(Debug)]
+pub enum ConnectionError {
+    Connect(String),
+    Unauthorized(String),
+    Rejected(String),
+    Closed(Option<String>),
+}
+
+impl fmt::Display for ConnectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConnectionError::Connect(e) => write!(f, "WebSocket connection failed: {}", e),
+            ConnectionError::Unauthorized(e) => write!(f, "Device token rejected: {}", e),
+            ConnectionError::Rejected(e) => write!(f, "Registration failed: {}", e),
+            ConnectionError::Closed(Some(reason)) => write!(f, "WebSocket connection closed: {}", reason),
+            ConnectionError::Closed(None) => write!(f, "WebSocket connection closed"),
+        }
+    }
+}
+
+impl std::error::Error for ConnectionError {}
+
+/// Адрес сокета с токеном устройства в query string, как делал Python-клиент
+pub fn socket_url(wss_url: &str, device_token: &str) -> Result<String, ConnectionError> {
+    let mut url = reqwest::Url::parse(wss_url).map_err(|e| ConnectionError::Connect(e.to_string()))?;
+    url.query_pairs_mut().append_pair("token", device_token);
+    Ok(url.into())
+}
+
+/// Одна сессия: подключение, регистрация и обмен сообщениями до разрыва.
+/// Ok(()) возвращается только при отмене через `cancel`.
+pub async fn run_session(
+    device: &DeviceCredentials,
+    runner: Arc<dyn TaskRunner>,
+    events: EventSink,
+    kill: Arc<KillSwitch>,
+    cancel: &CancellationToken,
+) -> Result<(), ConnectionError> {
+    let url = socket_url(&device.wss_url, &device.device_token)?;
+    let ws = tokio::select! {
+        _ = cancel.cancelled() => return Ok(()),
+        connected = tokio_tungstenite::connect_async(url.as_str()) => connected.map_err(connect_error)?.0,
+    };
+    let (mut sink, mut stream) = ws.split();
+
+    send(&mut sink, &ClientMessage::register(&device.device_id, &device.device_token)).await?;
+    let reply = tokio::select! {
+        _ = cancel.cancelled() => return Ok(()),
+        reply = tokio::time::timeout(REGISTER_TIMEOUT, next_text(&mut stream)) => {
+            reply.map_err(|_| ConnectionError::Connect("no register.ok within timeout".into()))??
+        }
+    };
+    if !matches!(ServerMessage::parse(&reply), Ok(ServerMessage::RegisterOk)) {
+        return Err(ConnectionError::Rejected(reply));
+    }
+    log::info!("device {} registered", device.device_id);
+    events(DriverEvent::Registered);
+
+    // Задачи выполняются отдельно, чтобы heartbeat не вставал на время длинной задачи
+    let (out_tx, mut out_rx) = mpsc::unbounded_channel::<ClientMessage>();
+    let (task_tx, task_rx) = mpsc::unbounded_channel::<TaskExec>();
+    let worker = tokio::spawn(task_worker(runner, task_rx, out_tx, events.clone(), kill));
+    let _worker = AbortOnDrop(worker);
+
+    let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => {
+                let _ = sink.send(Message::Close(None)).await;
+                return Ok(());
+            }
+            _ = heartbeat.tick() => {
+                send(&mut sink, &ClientMessage::heartbeat(&device.device_id)).await?;
+            }
+            Some(outgoing) = out_rx.recv() => {
+                send(&mut sink, &outgoing).await?;
+            }
+            incoming = stream.next() => match incoming {
+                Some(Ok(Message::Text(text))) => match ServerMessage::parse(&text) {
+                    Ok(ServerMessage::TaskExec(task)) => {
+                        log::info!("received task {} with {} actions", task.task_id, task.actions.len());
+                        let _ = task_tx.send(task);
+                    }
+                    Ok(_) => {}
+                    Err(e) => log::warn!("unparsable message from server: {}", e),
+                },
+                Some(Ok(Message::Close(frame))) => {
+                    return Err(ConnectionError::Closed(frame.map(|f| f.reason.to_string())));
+                }
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(ConnectionError::Closed(Some(e.to_string()))),
+                None => return Err(ConnectionError::Closed(None)),
+            }
+        }
+    }
+}
+
+// 401/403 на handshake означает, что токен устройства отозван: переподключаться бессмысленно
+fn connect_error(e: tungstenite::Error) -> ConnectionError {
+    match &e {
+        tungstenite::Error::Http(response) if matches!(response.status().as_u16(), 401 | 403) => {
+            ConnectionError::Unauthorized(e.to_string())
+        }
+        _ => ConnectionError::Connect(e.to_string()),
+    }
+}
+
+async fn task_worker(
+    runner: Arc<dyn TaskRunner>,
+    mut tasks: mpsc::UnboundedReceiver<TaskExec>,
+    out: mpsc::UnboundedSender<ClientMessage>,
+    events: EventSink,
+    kill: Arc<KillSwitch>,
+) {
+    loop {
+        let cancel = kill.token();
+        let task = tokio::select! {
+            task = tasks.recv() => match task {
+                Some(task) => task,
+                None => return,
+            },
+            // Остановка, когда ничего не выполнялось: следующая задача получит свежий токен
+            _ = cancel.cancelled() => {
+                kill.reset();
+                continue;
+            }
+        };
+        run_task(runner.as_ref(), &task, &cancel, &out, &events).await;
+
+        if cancel.is_cancelled() {
+            // Задачи, пришедшие до остановки, тоже отменяются: runner отвечает cancelled на каждое действие
+            while let Ok(queued) = tasks.try_recv() {
+                run_task(runner.as_ref(), &queued, &cancel, &out, &events).await;
+            }
+            kill.reset();
+        }
+    }
+}
+
+async fn run_task(
+    runner: &dyn TaskRunner,
+    task: &TaskExec,
+    cancel: &CancellationToken,
+    out: &mpsc::UnboundedSender<ClientMessage>,
+    events: &EventSink,
+) {
+    events(DriverEvent::TaskStarted { task_id: task.task_id.clone() });
+    let results = runner.run(task, cancel).await;
+    let status = if cancel.is_cancelled() { TaskStatus::Cancelled } else { TaskStatus::Completed };
+    let _ = out.send(ClientMessage::task_result(&task.task_id, status, results));
+    events(DriverEvent::TaskFinished { task_id: task.task_id.clone(), status });
+}
+
+async fn send<S>(sink: &mut S, message: &ClientMessage) -> Result<(), ConnectionError>
+where
+    S: Sink<Message> + Unpin,
+    S::Error: fmt::Display,
+{
+    sink.send(Message::Text(message.to_json().into()))
+        .await
+        .map_err(|e| ConnectionError::Closed(Some(e.to_string())))
+}
+
+async fn next_text<S>(stream: &mut S) -> Result<String, ConnectionError>
+where
+    S: Stream<Item = Result<Message, tungstenite::Error>> + Unpin,
+{
+    loop {
+        match stream.next().await {
+            Some(Ok(Message::Text(text))) => return Ok(text.to_string()),
+            Some(Ok(Message::Close(frame))) => {
+                return Err(ConnectionError::Closed(frame.map(|f| f.reason.to_string())));
+            }
+            Some(Ok(_)) => continue,
+            Some(Err(e)) => return Err(ConnectionError::Closed(Some(e.to_string()))),
+            None => return Err(ConnectionError::Closed(None)),
+        }
+    }
+}
+
+struct AbortOnDrop(tokio::task::JoinHandle<()>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+#[cfg(test)]
+// Сигнатуру callback handshake задаёт tungstenite
+#[allow(clippy::result_large_err)]
+mod tests {
+    use super::*;
+    use crate::agent::protocol::ActionSpec;
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+    use tokio_tungstenite::tungstenite::http;
+    use tokio_tungstenite::WebSocketStream;
+
+    const WAIT: Duration = Duration::from_secs(5);
+
+    struct EchoRunner;
+
+    impl TaskRunner for EchoRunner {
+        fn run<'a>(&'a self, task: &'a TaskExec, _cancel: &'a CancellationToken) -> BoxFuture<'a, Vec<ActionResult>> {
+            Box::pin(async move {
+                task.actions.iter().map(|a| ActionResult::done(a.action_id.clone().unwrap_or_default())).collect()
+            })
+        }
+    }
+
+    /// Держит задачу, пока её не отменят или не отпустят через release; о начале сообщает в started
+    struct BlockingRunner {
+        started: mpsc::UnboundedSender<String>,
+        release: tokio::sync::Notify,
+    }
+
+    impl TaskRunner for BlockingRunner {
+        fn run<'a>(&'a self, task: &'a TaskExec, cancel: &'a CancellationToken) -> BoxFuture<'a, Vec<ActionResult>> {
+            Box::pin(async move {
+                let _ = self.started.send(task.task_id.clone());
+                let cancelled = tokio::select! {
+                    _ = cancel.cancelled() => true,
+                    _ = self.release.notified() => false,
+                };
+                task.actions
+                    .iter()
+                    .map(|a| a.action_id.clone().unwrap_or_default())
+                    .map(|id| if cancelled { ActionResult::cancelled(id) } else { ActionResult::done(id) })
+                    .collect()
+            })
+        }
+    }
+
+    fn device(port: u16) -> DeviceCredentials {
+        DeviceCredentials {
+            device_id: "42".into(),
+            device_token: "secret".into(),
+            wss_url: format!("ws://127.0.0.1:{}/ws", port),
+            kid: None,
+            expires_at: None,
+        }
+    }
+
+    /// Запускает сессию против локального сервера; возвращает серверную сторону и URI handshake
+    async fn connect(
+        events: EventSink,
+        cancel: CancellationToken,
+    ) -> (WebSocketStream<TcpStream>, String, tokio::task::JoinHandle<Result<(), ConnectionError>>) {
+        connect_with(Arc::new(EchoRunner), Arc::default(), events, cancel).await
+    }
+
+    async fn connect_with(
+        runner: Arc<dyn TaskRunner>,
+        kill: Arc<KillSwitch>,
+        events: EventSink,
+        cancel: CancellationToken,
+    ) -> (WebSocketStream<TcpStream>, String, tokio::task::JoinHandle<Result<(), ConnectionError>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let device = device(listener.local_addr().unwrap().port());
+        let session = tokio::spawn(async move { run_session(&device, runner, events, kill, &cancel).await });
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut uri = String::new();
+        let ws = tokio_tungstenite::accept_hdr_async(stream, |request: &Request, response: Response| {
+            uri = request.uri().to_string();
+            Ok(response)
+        })
+        .await
+        .unwrap();
+        (ws, uri, session)
+    }
+
+    /// Следующее сообщение клиента, кроме heartbeat
+    async fn receive(ws: &mut WebSocketStream<TcpStream>) -> serde_json::Value {
+        loop {
+            let message = tokio::time::timeout(WAIT, ws.next()).await.unwrap().unwrap().unwrap();
+            let Message::Text(text) = message else { continue };
+            let value: serde_json::Value = serde_json::from_str(&text).unwrap();
+            if value["type"] != "heartbeat" {
+                return value;
+            }
+        }
+    }
+
+    async fn reply(ws: &mut WebSocketStream<TcpStream>, value: serde_json::Value) {
+        ws.send(Message::Text(value.to_string().into())).await.unwrap();
+    }
+
+    fn task(task_id: &str) -> serde_json::Value {
+        serde_json::json!({
+            "type": "task.exec",
+            "task_id": task_id,
+            "actions": [{ "action_id": format!("{}-a", task_id), "type": "click" }],
+        })
+    }
+
+    fn recorder() -> (EventSink, Arc<Mutex<Vec<DriverEvent>>>) {
+        let recorded = Arc::new(Mutex::new(Vec::new()));
+        let sink = recorded.clone();
+        (Arc::new(move |event| sink.lock().unwrap().push(event)), recorded)
+    }
+
+    #[tokio::test]
+    async fn registers_with_device_token() {
+        let (events, recorded) = recorder();
+        let cancel = CancellationToken::new();
+        let (mut ws, uri, session) = connect(events, cancel.clone()).await;
+
+        assert_eq!(uri, "/ws?token=secret");
+        let register = receive(&mut ws).await;
+        assert_eq!(register["type"], "register");
+        assert_eq!(register["device_id"], "42");
+        assert_eq!(register["device_token"], "secret");
+
+        reply(&mut ws, serde_json::json!({ "type": "register.ok" })).await;
+        // Первый heartbeat уходит сразу после регистрации
+        let heartbeat = tokio::time::timeout(WAIT, ws.next()).await.unwrap().unwrap().unwrap();
+        assert!(heartbeat.to_text().unwrap().contains("\"heartbeat\""));
+        assert!(matches!(recorded.lock().unwrap()[..], [DriverEvent::Registered]));
+
+        cancel.cancel();
+        assert!(tokio::time::timeout(WAIT, session).await.unwrap().unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn task_exec_is_answered_with_task_result() {
+        let (events, recorded) = recorder();
+        let (mut ws, _, _session) = connect(events, CancellationToken::new()).await;
+        receive(&mut ws).await;
+        reply(&mut ws, serde_json::json!({ "type": "register.ok" })).await;
+
+        let task = TaskExec {
+            task_id: "t-1".into(),
+            actions: vec![ActionSpec { action_id: Some("a-1".into()), action_type: "screenshot".into(), params: Default::default() }],
+        };
+        reply(&mut ws, serde_json::json!({ "type": "task.exec", "task_id": task.task_id, "actions": task.actions })).await;
+
+        let result = receive(&mut ws).await;
+        assert_eq!(result["type"], "task.result");
+        assert_eq!(result["task_id"], "t-1");
+        assert_eq!(result["status"], "completed");
+        assert_eq!(result["results"][0]["action_id"], "a-1");
+        assert_eq!(result["results"][0]["status"], "done");
+        let recorded = recorded.lock().unwrap();
+        assert!(matches!(&recorded[1], DriverEvent::TaskStarted { task_id } if task_id == "t-1"));
+        assert!(matches!(&recorded[2], DriverEvent::TaskFinished { status: TaskStatus::Completed, .. }));
+    }
+
+    #[tokio::test]
+    async fn kill_switch_cancels_running_and_queued_tasks() {
+        let (started_tx, mut started) = mpsc::unbounded_channel();
+        let runner = Arc::new(BlockingRunner { started: started_tx, release: tokio::sync::Notify::new() });
+        let kill = Arc::new(KillSwitch::default());
+        let (events, _) = recorder();
+        let (mut ws, _, _session) = connect_with(runner.clone(), kill.clone(), events, CancellationToken::new()).await;
+        receive(&mut ws).await;
+        reply(&mut ws, serde_json::json!({ "type": "register.ok" })).await;
+
+        reply(&mut ws, task("t-1")).await;
+        assert_eq!(tokio::time::timeout(WAIT, started.recv()).await.unwrap().unwrap(), "t-1");
+        reply(&mut ws, task("t-2")).await;
+        // Pong приходит, только когда сессия прочитала всё до ping: t-2 уже в очереди воркера
+        ws.send(Message::Ping(Vec::new().into())).await.unwrap();
+        loop {
+            let message = tokio::time::timeout(WAIT, ws.next()).await.unwrap().unwrap().unwrap();
+            if matches!(message, Message::Pong(_)) {
+                break;
+            }
+        }
+
+        kill.trigger();
+        for task_id in ["t-1", "t-2"] {
+            let result = receive(&mut ws).await;
+            assert_eq!(result["task_id"], task_id);
+            assert_eq!(result["status"], "cancelled");
+            assert_eq!(result["results"][0]["status"], "cancelled");
+        }
+
+        // После остановки переключатель сброшен: следующая задача выполняется как обычно
+        reply(&mut ws, task("t-3")).await;
+        loop {
+            let task_id = tokio::time::timeout(WAIT, started.recv()).await.unwrap().unwrap();
+            if task_id == "t-3" {
+                break;
+            }
+        }
+        runner.release.notify_one();
+        let result = receive(&mut ws).await;
+        assert_eq!(result["task_id"], "t-3");
+        assert_eq!(result["status"], "completed");
+        assert_eq!(result["results"][0]["status"], "done");
+    }
+
+    #[tokio::test]
+    async fn server_close_ends_session_for_reconnect() {
+        let (events, _) = recorder();
+        let (mut ws, _, session) = connect(events, CancellationToken::new()).await;
+        receive(&mut ws).await;
+        reply(&mut ws, serde_json::json!({ "type": "register.ok" })).await;
+
+        ws.close(None).await.unwrap();
+
+        // Closed — переподключаемая ошибка: supervise уходит в backoff и пробует снова
+        let result = tokio::time::timeout(WAIT, session).await.unwrap().unwrap();
+        assert!(matches!(result, Err(ConnectionError::Closed(_))));
+    }
+
+    #[tokio::test]
+    async fn rejected_registration_is_not_retried() {
+        let (events, recorded) = recorder();
+        let (mut ws, _, session) = connect(events, CancellationToken::new()).await;
+        receive(&mut ws).await;
+
+        reply(&mut ws, serde_json::json!({ "type": "register.error", "error": "unknown device" })).await;
+
+        let result = tokio::time::timeout(WAIT, session).await.unwrap().unwrap();
+        assert!(matches!(result, Err(ConnectionError::Rejected(_))));
+        assert!(recorded.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn revoked_token_is_unauthorized() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let device = device(listener.local_addr().unwrap().port());
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let _ = tokio_tungstenite::accept_hdr_async(stream, |_: &Request, _: Response| {
+                Err::<Response, ErrorResponse>(http::Response::builder().status(401).body(None).unwrap())
+            })
+            .await;
+        });
+
+        let (events, _) = recorder();
+        let result = run_session(&device, Arc::new(EchoRunner), events, Arc::default(), &CancellationToken::new()).await;
+
+        assert!(matches!(result, Err(ConnectionError::Unauthorized(_))));
+    }
+}
@@ -0,0 +1,138 @@
+// Выполнение задач task.exec: проверка политики, затем действия по очереди, результат на каждое действие
+use futures_util::future::BoxFuture;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio_util::sync::CancellationToken;
+
+use super::audit::{self, AuditKind, AuditLog, AuditRecord};
+use super::connection::TaskRunner;
+use super::executor::{self, Action, ActionError, ExecutorRegistry};
+use super::policy::{Policy, Verdict};
+use super::protocol::{ActionResult, TaskExec};
+
+/// Что сейчас делает агент; по этому переключается индикация (рамка вокруг экрана)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Activity {
+    Working,
+    AwaitingApproval,
+    Idle,
+    Failed,
+    Cancelled,
+}
+
+pub type ActivityHook = Arc<dyn Fn(Activity) + Send + Sync>;
+
+pub struct Dispatcher {
+    executors: Arc<ExecutorRegistry>,
+    policy: Arc<Policy>,
+    audit: Arc<AuditLog>,
+    activity: Option<ActivityHook>,
+}
+
+impl Dispatcher {
+    pub fn new(executors: Arc<ExecutorRegistry>, policy: Arc<Policy>, audit: Arc<AuditLog>) -> Self {
+        Self { executors, policy, audit, activity: None }
+    }
+
+    pub fn on_activity(mut self, hook: ActivityHook) -> Self {
+        self.activity = Some(hook);
+        self
+    }
+
+    fn report(&self, activity: Activity) {
+        if let Some(hook) = &self.activity {
+            hook(activity);
+        }
+    }
+}
+
+impl TaskRunner for Dispatcher {
+    fn run<'a>(&'a self, task: &'a TaskExec, cancel: &'a CancellationToken) -> BoxFuture<'a, Vec<ActionResult>> {
+        Box::pin(async move {
+            let started = Instant::now();
+            self.report(Activity::Working);
+            self.audit.append(AuditRecord {
+                params: Some(serde_json::json!({ "actions": task.actions.len() })),
+                ..AuditRecord::new(AuditKind::TaskReceived, &task.task_id)
+            });
+
+            // Вердикты фиксируются до вопроса пользователю: "разрешить один раз" не меняет правил
+            let parsed: Vec<(String, Result<Action, ActionError>, Verdict)> = task
+                .actions
+                .iter()
+                .map(|spec| {
+                    let action_id = spec.action_id.clone().unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+                    let action = Action::parse(spec);
+                    let verdict = action.as_ref().map_or(Verdict::Allow, |a| self.policy.check(a.kind()));
+                    (action_id, action, verdict)
+                })
+                .collect();
+
+            let risky: Vec<&Action> = parsed
+                .iter()
+                .filter(|(_, _, verdict)| *verdict == Verdict::Ask)
+                .filter_map(|(_, action, _)| action.as_ref().ok())
+                .collect();
+            let approved = risky.is_empty() || cancel.is_cancelled() || {
+                self.report(Activity::AwaitingApproval);
+                // Отмена закрывает и запрос подтверждения: PendingGuard уберёт его из очереди
+                let approved = tokio::select! {
+                    approval = self.policy.request_approval(&task.task_id, &risky) => approval.allows(),
+                    _ = cancel.cancelled() => false,
+                };
+                self.report(Activity::Working);
+                approved
+            };
+
+            let mut failed = false;
+            let mut results = Vec::with_capacity(parsed.len());
+            for ((action_id, action, verdict), spec) in parsed.into_iter().zip(&task.actions) {
+                let action_started = Instant::now();
+                let result = match (action, verdict) {
+                    _ if cancel.is_cancelled() => Err(ActionError::Cancelled),
+                    (Err(e), _) => Err(e),
+                    (Ok(_), Verdict::Deny) => Err(ActionError::Denied("blocked by policy rule".into())),
+                    (Ok(_), Verdict::Ask) if !approved => Err(ActionError::Denied("not approved by user".into())),
+                    (Ok(action), _) => tokio::select! {
+                        result = self.executors.run(&action) => result,
+                        _ = cancel.cancelled() => Err(ActionError::Cancelled),
+                    },
+                };
+                let status = match &result {
+                    Ok(_) => "done",
+                    Err(ActionError::Denied(_)) => "denied",
+                    Err(ActionError::Cancelled) => "cancelled",
+                    Err(_) => {
+                        failed = true;
+                        "failed"
+                    }
+                };
+                self.audit.append(AuditRecord {
+                    action_id: Some(action_id.clone()),
+                    action_type: Some(spec.action_type.clone()),
+                    params: Some(audit::redact(&spec.params)),
+                    status: Some(status.into()),
+                    error: result.as_ref().err().map(|e| e.to_string()),
+                    duration_ms: Some(action_started.elapsed().as_millis() as u64),
+                    ..AuditRecord::new(AuditKind::Action, &task.task_id)
+                });
+                results.push(executor::to_result(&action_id, result));
+            }
+
+            self.audit.append(AuditRecord {
+                status: Some(if cancel.is_cancelled() { "cancelled" } else { "completed" }.into()),
+                duration_ms: Some(started.elapsed().as_millis() as u64),
+                ..AuditRecord::new(AuditKind::TaskFinished, &task.task_id)
+            });
+            // Отказ политики — штатный исход, ошибкой задачи не считается
+            self.report(if cancel.is_cancelled() {
+                Activity::Cancelled
+            } else if failed {
+                Activity::Failed
+            } else {
+                Activity::Idle
+            });
+            results
+        })
+    }
+}
@@ -0,0 +1,52 @@
+// Детерминированный бекенд без доступа к рабочему столу: запоминает действия и отдаёт фиксированные ответы
+use futures_util::future::BoxFuture;
+use std::sync::Mutex;
+
+use super::{Action, ActionError, ActionExecutor, ActionKind, ActionOutput};
+
+#[derive(Default)]
+pub struct FakeBackend {
+    // Читается только тестами, в сборке с SKYGEN_FAKE_DESKTOP=1 просто копится
+    #[cfg_attr(not(test), allow(dead_code))]
+    executed: Mutex<Vec<Action>>,
+}
+
+impl FakeBackend {
+    /// Действия в порядке выполнения
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn executed(&self) -> Vec<Action> {
+        self.executed.lock().unwrap().clone()
+    }
+}
+
+impl ActionExecutor for FakeBackend {
+    fn name(&self) -> &'static str {
+        "fake"
+    }
+
+    fn supports(&self, _kind: ActionKind) -> bool {
+        true
+    }
+
+    fn execute<'a>(&'a self, action: &'a Action) -> BoxFuture<'a, Result<ActionOutput, ActionError>> {
+        Box::pin(async move {
+            self.executed.lock().unwrap().push(action.clone());
+            let output = match action {
+                Action::Screenshot(_) => ActionOutput {
+                    data: None,
+                    meta: Some(serde_json::json!({ "width": 1, "height": 1, "format": "png" })),
+                },
+                Action::Accessibility {} => ActionOutput {
+                    data: Some(serde_json::json!("<desktop-frame/>")),
+                    meta: None,
+                },
+                Action::TypeText { text } => ActionOutput {
+                    data: None,
+                    meta: Some(serde_json::json!({ "text_length": text.chars().count() })),
+                },
+                _ => ActionOutput::default(),
+            };
+            Ok(output)
+        })
+    }
+}
@@ -0,0 +1,287 @@
+// Ввод на Linux через системные утилиты: xdotool под X11, wtype/ydotool под Wayland
+use futures_util::future::BoxFuture;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+use super::{Action, ActionError, ActionExecutor, ActionKind, ActionOutput, MouseButton};
+
+fn find_in_path(binary: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path).map(|dir| dir.join(binary)).find(|p| p.is_file())
+}
+
+async fn run(program: &Path, args: &[String]) -> Result<(), ActionError> {
+    // Аварийная остановка бросает future: утилита ввода должна умереть вместе с ним
+    let output = Command::new(program)
+        .args(args)
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| ActionError::Failed(format!("Failed to run {}: {}", program.display(), e)))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(ActionError::Failed(format!(
+            "{} exited with {}: {}",
+            program.display(),
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )))
+    }
+}
+
+fn typed(text: &str) -> ActionOutput {
+    ActionOutput { data: None, meta: Some(serde_json::json!({ "text_length": text.chars().count() })) }
+}
+
+pub struct X11Backend {
+    xdotool: PathBuf,
+}
+
+impl X11Backend {
+    pub fn detect() -> Option<Self> {
+        std::env::var_os("DISPLAY")?;
+        Some(Self { xdotool: find_in_path("xdotool")? })
+    }
+
+    async fn perform(&self, action: &Action) -> Result<ActionOutput, ActionError> {
+        let args = xdotool_args(action)?;
+        if !args.is_empty() {
+            run(&self.xdotool, &args).await?;
+        }
+        Ok(match action {
+            Action::TypeText { text } => typed(text),
+            _ => ActionOutput::default(),
+        })
+    }
+}
+
+/// Аргументы xdotool для действия; пустой список — делать нечего (прокрутка на 0)
+fn xdotool_args(action: &Action) -> Result<Vec<String>, ActionError> {
+    Ok(match action {
+        Action::TypeText { text } => {
+            vec!["type".into(), "--clearmodifiers".into(), "--".into(), text.clone()]
+        }
+        // xdotool понимает запись вида ctrl+shift+t как есть
+        Action::KeyPress { keys } => vec!["key".into(), "--clearmodifiers".into(), keys.clone()],
+        Action::MouseMove { x, y } => vec!["mousemove".into(), x.to_string(), y.to_string()],
+        Action::Click { x, y, button, count } => {
+            let mut args = Vec::new();
+            if let (Some(x), Some(y)) = (x, y) {
+                args.extend(["mousemove".into(), x.to_string(), y.to_string()]);
+            }
+            let button = match button {
+                MouseButton::Left => "1",
+                MouseButton::Middle => "2",
+                MouseButton::Right => "3",
+            };
+            args.extend(["click".into(), "--repeat".into(), count.max(&1).to_string(), button.into()]);
+            args
+        }
+        Action::Scroll { dx, dy } => {
+            // Колесо в X11 — это кнопки 4/5 (вертикаль) и 6/7 (горизонталь)
+            let mut args = Vec::new();
+            for (delta, negative, positive) in [(*dy, "4", "5"), (*dx, "6", "7")] {
+                if delta != 0 {
+                    let button = if delta < 0 { negative } else { positive };
+                    args.extend(["click".into(), "--repeat".into(), delta.abs().to_string(), button.into()]);
+                }
+            }
+            args
+        }
+        other => return Err(unsupported("x11", other)),
+    })
+}
+
+impl ActionExecutor for X11Backend {
+    fn name(&self) -> &'static str {
+        "x11"
+    }
+
+    fn supports(&self, kind: ActionKind) -> bool {
+        matches!(
+            kind,
+            ActionKind::TypeText | ActionKind::KeyPress | ActionKind::MouseMove | ActionKind::Click | ActionKind::Scroll
+        )
+    }
+
+    fn execute<'a>(&'a self, action: &'a Action) -> BoxFuture<'a, Result<ActionOutput, ActionError>> {
+        Box::pin(self.perform(action))
+    }
+}
+
+/// wtype работает через virtual-keyboard протокол композитора, мышь есть только у ydotool
+pub struct WaylandBackend {
+    wtype: PathBuf,
+    ydotool: Option<PathBuf>,
+}
+
+impl WaylandBackend {
+    pub fn detect() -> Option<Self> {
+        std::env::var_os("WAYLAND_DISPLAY")?;
+        Some(Self { wtype: find_in_path("wtype")?, ydotool: find_in_path("ydotool") })
+    }
+
+    fn ydotool(&self) -> Result<&Path, ActionError> {
+        self.ydotool
+            .as_deref()
+            .ok_or_else(|| ActionError::Unsupported("Mouse input on Wayland requires ydotool".into()))
+    }
+
+    async fn perform(&self, action: &Action) -> Result<ActionOutput, ActionError> {
+        match action {
+            Action::TypeText { text } => {
+                run(&self.wtype, &["--".into(), text.clone()]).await?;
+                return Ok(typed(text));
+            }
+            Action::KeyPress { keys } => run(&self.wtype, &wtype_chord(keys)?).await?,
+            Action::MouseMove { .. } | Action::Click { .. } | Action::Scroll { .. } => {
+                let ydotool = self.ydotool()?;
+                for args in ydotool_commands(action)? {
+                    run(ydotool, &args).await?;
+                }
+            }
+            other => return Err(unsupported(self.name(), other)),
+        }
+        Ok(ActionOutput::default())
+    }
+}
+
+/// Вызовы ydotool для действий мыши, по порядку
+fn ydotool_commands(action: &Action) -> Result<Vec<Vec<String>>, ActionError> {
+    let mouse_move =
+        |x: &i32, y: &i32| ["mousemove", "--absolute", "-x", &x.to_string(), "-y", &y.to_string()].map(String::from).to_vec();
+    Ok(match action {
+        Action::MouseMove { x, y } => vec![mouse_move(x, y)],
+        Action::Click { x, y, button, count } => {
+            let mut commands = Vec::new();
+            if let (Some(x), Some(y)) = (x, y) {
+                commands.push(mouse_move(x, y));
+            }
+            // Коды ydotool: 0xC0 — нажатие и отпускание левой кнопки, C1 — правой, C2 — средней
+            let code = match button {
+                MouseButton::Left => "0xC0",
+                MouseButton::Right => "0xC1",
+                MouseButton::Middle => "0xC2",
+            };
+            commands.push(["click", "--repeat", &count.max(&1).to_string(), code].map(String::from).to_vec());
+            commands
+        }
+        Action::Scroll { dx, dy } => {
+            vec![["mousemove", "--wheel", "-x", &dx.to_string(), "-y", &dy.to_string()].map(String::from).to_vec()]
+        }
+        other => return Err(unsupported("wayland", other)),
+    })
+}
+
+impl ActionExecutor for WaylandBackend {
+    fn name(&self) -> &'static str {
+        "wayland"
+    }
+
+    fn supports(&self, kind: ActionKind) -> bool {
+        match kind {
+            ActionKind::TypeText | ActionKind::KeyPress => true,
+            ActionKind::MouseMove | ActionKind::Click | ActionKind::Scroll => self.ydotool.is_some(),
+            _ => false,
+        }
+    }
+
+    fn execute<'a>(&'a self, action: &'a Action) -> BoxFuture<'a, Result<ActionOutput, ActionError>> {
+        Box::pin(self.perform(action))
+    }
+}
+
+/// "ctrl+shift+t" -> wtype -M ctrl -M shift -k t -m shift -m ctrl
+fn wtype_chord(keys: &str) -> Result<Vec<String>, ActionError> {
+    let parts: Vec<&str> = keys.split('+').map(str::trim).filter(|p| !p.is_empty()).collect();
+    let (key, modifiers) = parts
+        .split_last()
+        .ok_or_else(|| ActionError::InvalidParams("keys must not be empty".into()))?;
+
+    let mut args = Vec::new();
+    for modifier in modifiers {
+        args.extend(["-M".to_string(), modifier.to_lowercase()]);
+    }
+    args.extend(["-k".to_string(), key.to_string()]);
+    for modifier in modifiers.iter().rev() {
+        args.extend(["-m".to_string(), modifier.to_lowercase()]);
+    }
+    Ok(args)
+}
+
+fn unsupported(backend: &str, action: &Action) -> ActionError {
+    ActionError::Unsupported(format!("{:?} is not supported by the {} backend", action.kind(), backend))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn wtype_chord_presses_modifiers_around_key() {
+        let cases: [(&str, &[&str]); 4] = [
+            ("Return", &["-k", "Return"]),
+            ("ctrl+c", &["-M", "ctrl", "-k", "c", "-m", "ctrl"]),
+            ("Ctrl + Shift + t", &["-M", "ctrl", "-M", "shift", "-k", "t", "-m", "shift", "-m", "ctrl"]),
+            ("alt++F4", &["-M", "alt", "-k", "F4", "-m", "alt"]),
+        ];
+        for (keys, expected) in cases {
+            assert_eq!(wtype_chord(keys).unwrap(), args(expected), "{}", keys);
+        }
+        assert!(matches!(wtype_chord(" + "), Err(ActionError::InvalidParams(_))));
+    }
+
+    #[test]
+    fn xdotool_maps_buttons_and_wheel() {
+        let click = |button, count| Action::Click { x: None, y: None, button, count };
+        let cases: [(Action, &[&str]); 6] = [
+            (click(MouseButton::Left, 1), &["click", "--repeat", "1", "1"]),
+            (click(MouseButton::Middle, 0), &["click", "--repeat", "1", "2"]),
+            (click(MouseButton::Right, 2), &["click", "--repeat", "2", "3"]),
+            (
+                Action::Click { x: Some(5), y: Some(7), button: MouseButton::Left, count: 1 },
+                &["mousemove", "5", "7", "click", "--repeat", "1", "1"],
+            ),
+            (Action::Scroll { dx: 2, dy: -3 }, &["click", "--repeat", "3", "4", "click", "--repeat", "2", "7"]),
+            (Action::Scroll { dx: 0, dy: 0 }, &[]),
+        ];
+        for (action, expected) in cases {
+            assert_eq!(xdotool_args(&action).unwrap(), args(expected), "{:?}", action);
+        }
+        assert_eq!(
+            xdotool_args(&Action::TypeText { text: "-x".into() }).unwrap(),
+            args(&["type", "--clearmodifiers", "--", "-x"])
+        );
+    }
+
+    #[test]
+    fn ydotool_maps_buttons_and_moves_first() {
+        let cases: [(MouseButton, &str); 3] =
+            [(MouseButton::Left, "0xC0"), (MouseButton::Right, "0xC1"), (MouseButton::Middle, "0xC2")];
+        for (button, code) in cases {
+            let commands = ydotool_commands(&Action::Click { x: None, y: None, button, count: 1 }).unwrap();
+            assert_eq!(commands, [args(&["click", "--repeat", "1", code])]);
+        }
+
+        let commands = ydotool_commands(&Action::Click { x: Some(10), y: Some(20), button: MouseButton::Left, count: 3 }).unwrap();
+        assert_eq!(
+            commands,
+            [args(&["mousemove", "--absolute", "-x", "10", "-y", "20"]), args(&["click", "--repeat", "3", "0xC0"])]
+        );
+        assert_eq!(
+            ydotool_commands(&Action::Scroll { dx: 0, dy: -2 }).unwrap(),
+            [args(&["mousemove", "--wheel", "-x", "0", "-y", "-2"])]
+        );
+    }
+
+    #[test]
+    fn other_actions_are_unsupported() {
+        assert!(matches!(xdotool_args(&Action::Accessibility {}), Err(ActionError::Unsupported(_))));
+        assert!(matches!(ydotool_commands(&Action::KeyPress { keys: "a".into() }), Err(ActionError::Unsupported(_))));
+    }
+}
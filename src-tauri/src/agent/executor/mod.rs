@@ -0,0 +1,378 @@
+// Исполнители действий: типизированные действия и реестр платформенных бекендов
+use futures_util::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+use super::protocol::{ActionResult, ActionSpec};
+use crate::capture::{ImageFormat, Region};
+
+pub mod fake;
+#[cfg(target_os = "linux")]
+pub mod linux;
+pub mod python;
+pub mod screen;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MouseButton {
+    #[default]
+    Left,
+    Right,
+    Middle,
+}
+
+fn one() -> u8 {
+    1
+}
+
+/// Параметры screenshot: область важнее монитора, без обоих снимаются все мониторы.
+/// Без upload_url снимок возвращается в task.result как base64.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScreenshotParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monitor: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<Region>,
+    pub format: ImageFormat,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_height: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upload_url: Option<String>,
+}
+
+/// Действие из task.exec после разбора параметров
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "params", rename_all = "snake_case")]
+pub enum Action {
+    Screenshot(ScreenshotParams),
+    Accessibility {},
+    TypeText { text: String },
+    KeyPress { keys: String },
+    MouseMove { x: i32, y: i32 },
+    Click {
+        #[serde(default)]
+        x: Option<i32>,
+        #[serde(default)]
+        y: Option<i32>,
+        #[serde(default)]
+        button: MouseButton,
+        #[serde(default = "one")]
+        count: u8,
+    },
+    Scroll {
+        #[serde(default)]
+        dx: i32,
+        #[serde(default)]
+        dy: i32,
+    },
+    /// Действие action runner'а desktop_env как есть
+    #[serde(rename = "execute_action")]
+    DesktopEnv { action: serde_json::Value },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionKind {
+    Screenshot,
+    Accessibility,
+    TypeText,
+    KeyPress,
+    MouseMove,
+    Click,
+    Scroll,
+    ExecuteAction,
+}
+
+impl ActionKind {
+    pub const ALL: &'static [ActionKind] = &[
+        ActionKind::Screenshot,
+        ActionKind::Accessibility,
+        ActionKind::TypeText,
+        ActionKind::KeyPress,
+        ActionKind::MouseMove,
+        ActionKind::Click,
+        ActionKind::Scroll,
+        ActionKind::ExecuteAction,
+    ];
+
+    /// Имя в поле type протокола
+    pub fn name(self) -> &'static str {
+        match self {
+            ActionKind::Screenshot => "screenshot",
+            ActionKind::Accessibility => "accessibility",
+            ActionKind::TypeText => "type_text",
+            ActionKind::KeyPress => "key_press",
+            ActionKind::MouseMove => "mouse_move",
+            ActionKind::Click => "click",
+            ActionKind::Scroll => "scroll",
+            ActionKind::ExecuteAction => "execute_action",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|kind| kind.name() == name)
+    }
+}
+
+impl Action {
+    /// Разбирает действие из протокола; params может отсутствовать у действий без параметров
+    pub fn parse(spec: &ActionSpec) -> Result<Self, ActionError> {
+        let params = match &spec.params {
+            serde_json::Value::Null => serde_json::json!({}),
+            params => params.clone(),
+        };
+        // Тип проверяется отдельно, до разбора параметров: неизвестное действие — не ошибка параметров
+        let kind = ActionKind::from_name(&spec.action_type)
+            .ok_or_else(|| ActionError::Unsupported(format!("Unknown action type: {}", spec.action_type)))?;
+        serde_json::from_value(serde_json::json!({ "type": kind.name(), "params": params }))
+            .map_err(|e| ActionError::InvalidParams(e.to_string()))
+    }
+
+    pub fn kind(&self) -> ActionKind {
+        match self {
+            Action::Screenshot(_) => ActionKind::Screenshot,
+            Action::Accessibility {} => ActionKind::Accessibility,
+            Action::TypeText { .. } => ActionKind::TypeText,
+            Action::KeyPress { .. } => ActionKind::KeyPress,
+            Action::MouseMove { .. } => ActionKind::MouseMove,
+            Action::Click { .. } => ActionKind::Click,
+            Action::Scroll { .. } => ActionKind::Scroll,
+            Action::DesktopEnv { .. } => ActionKind::ExecuteAction,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ActionOutput {
+    pub data: Option<serde_json::Value>,
+    pub meta: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone)]
+pub enum ActionError {
+    Unsupported(String),
+    InvalidParams(String),
+    Denied(String),
+    Failed(String),
+    Cancelled,
+}
+
+impl fmt::Display for ActionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ActionError::Unsupported(e) => write!(f, "{}", e),
+            ActionError::InvalidParams(e) => write!(f, "Invalid action params: {}", e),
+            ActionError::Denied(e) => write!(f, "Action denied: {}", e),
+            ActionError::Failed(e) => write!(f, "{}", e),
+            ActionError::Cancelled => write!(f, "Action cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for ActionError {}
+
+/// Платформенный бекенд. Новые действия добавляются в Action и в нужные бекенды,
+/// цикл диспетчеризации при этом не меняется.
+pub trait ActionExecutor: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn supports(&self, kind: ActionKind) -> bool;
+    fn execute<'a>(&'a self, action: &'a Action) -> BoxFuture<'a, Result<ActionOutput, ActionError>>;
+}
+
+/// Реестр бекендов: для каждого вида действия используется первый зарегистрированный бекенд,
+/// который его поддерживает. Бекенды могут добавляться и после запуска агента, например Python.
+#[derive(Default)]
+pub struct ExecutorRegistry {
+    backends: RwLock<Vec<Arc<dyn ActionExecutor>>>,
+    routes: RwLock<HashMap<ActionKind, Arc<dyn ActionExecutor>>>,
+}
+
+impl ExecutorRegistry {
+    pub fn register(&self, backend: Arc<dyn ActionExecutor>) {
+        let mut routes = self.routes.write().unwrap();
+        for kind in ActionKind::ALL {
+            if backend.supports(*kind) {
+                routes.entry(*kind).or_insert_with(|| backend.clone());
+            }
+        }
+        log::info!("action backend loaded: {}", backend.name());
+        self.backends.write().unwrap().push(backend);
+    }
+
+    /// Собирает реестр под текущую платформу без Python: его проверка запускает интерпретатор
+    /// и идёт отдельно, через detect_python. SKYGEN_FAKE_DESKTOP=1 подключает только фейковый бекенд.
+    pub fn detect() -> Self {
+        let registry = Self::default();
+        if fake_desktop() {
+            registry.register(Arc::new(fake::FakeBackend::default()));
+            return registry;
+        }
+
+        registry.register(Arc::new(screen::ScreenBackend::default()));
+
+        #[cfg(target_os = "linux")]
+        {
+            if let Some(backend) = linux::X11Backend::detect() {
+                registry.register(Arc::new(backend));
+            } else if let Some(backend) = linux::WaylandBackend::detect() {
+                registry.register(Arc::new(backend));
+            }
+        }
+        registry
+    }
+
+    /// Подключает desktop_env, если скрипт отвечает; блокирует поток на время проверки
+    pub fn detect_python(&self, script: PathBuf) {
+        if fake_desktop() {
+            return;
+        }
+        if let Some(backend) = python::PythonBackend::detect(script) {
+            self.register(Arc::new(backend));
+        }
+    }
+
+    pub fn backend_names(&self) -> Vec<&'static str> {
+        self.backends.read().unwrap().iter().map(|b| b.name()).collect()
+    }
+
+    /// Есть ли чем управлять рабочим столом: desktop_env или бекенд ввода. Снимки экрана есть всегда и не считаются.
+    pub fn controls_desktop(&self) -> bool {
+        let routes = self.routes.read().unwrap();
+        [ActionKind::ExecuteAction, ActionKind::TypeText, ActionKind::Click].iter().any(|kind| routes.contains_key(kind))
+    }
+
+    pub async fn run(&self, action: &Action) -> Result<ActionOutput, ActionError> {
+        // Бекенд берётся из-под блокировки, а выполняется без неё
+        let backend = self.routes.read().unwrap().get(&action.kind()).cloned();
+        match backend {
+            Some(backend) => backend.execute(action).await,
+            None => Err(ActionError::Unsupported(format!("No backend available for action: {:?}", action.kind()))),
+        }
+    }
+}
+
+fn fake_desktop() -> bool {
+    std::env::var("SKYGEN_FAKE_DESKTOP").as_deref() == Ok("1")
+}
+
+pub fn to_result(action_id: &str, result: Result<ActionOutput, ActionError>) -> ActionResult {
+    match result {
+        Ok(output) => ActionResult { data: output.data, meta: output.meta, ..ActionResult::done(action_id) },
+        Err(ActionError::Cancelled) => ActionResult::cancelled(action_id),
+        Err(e) => ActionResult::failed(action_id, e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::protocol::ActionStatus;
+    use fake::FakeBackend;
+
+    fn spec(action_type: &str, params: serde_json::Value) -> ActionSpec {
+        ActionSpec { action_id: None, action_type: action_type.into(), params }
+    }
+
+    #[test]
+    fn kind_names_match_protocol() {
+        for kind in ActionKind::ALL {
+            assert_eq!(serde_json::to_value(kind).unwrap(), kind.name());
+            assert_eq!(ActionKind::from_name(kind.name()), Some(*kind));
+        }
+    }
+
+    #[test]
+    fn parses_known_actions() {
+        let click = Action::parse(&spec("click", serde_json::json!({ "x": 10, "y": 20 }))).unwrap();
+        assert_eq!(click, Action::Click { x: Some(10), y: Some(20), button: MouseButton::Left, count: 1 });
+        // У действий без параметров params может не быть вовсе
+        let tree = Action::parse(&spec("accessibility", serde_json::Value::Null)).unwrap();
+        assert_eq!(tree.kind(), ActionKind::Accessibility);
+    }
+
+    #[test]
+    fn unknown_type_is_unsupported() {
+        let error = Action::parse(&spec("format_disk", serde_json::json!({}))).unwrap_err();
+        assert!(matches!(error, ActionError::Unsupported(e) if e.contains("format_disk")));
+    }
+
+    #[test]
+    fn bad_params_are_invalid() {
+        let error = Action::parse(&spec("type_text", serde_json::json!({ "txt": "hi" }))).unwrap_err();
+        assert!(matches!(error, ActionError::InvalidParams(_)));
+    }
+
+    #[tokio::test]
+    async fn registry_runs_actions_on_fake_backend() {
+        let fake = Arc::new(FakeBackend::default());
+        let registry = ExecutorRegistry::default();
+        registry.register(fake.clone());
+
+        let typed = registry.run(&Action::TypeText { text: "héllo".into() }).await.unwrap();
+        registry.run(&Action::Scroll { dx: 0, dy: -3 }).await.unwrap();
+
+        assert_eq!(typed.meta, Some(serde_json::json!({ "text_length": 5 })));
+        assert_eq!(fake.executed(), [Action::TypeText { text: "héllo".into() }, Action::Scroll { dx: 0, dy: -3 }]);
+    }
+
+    #[tokio::test]
+    async fn first_registered_backend_wins() {
+        let (first, second) = (Arc::new(FakeBackend::default()), Arc::new(FakeBackend::default()));
+        let registry = ExecutorRegistry::default();
+        registry.register(first.clone());
+        registry.register(second.clone());
+
+        registry.run(&Action::MouseMove { x: 1, y: 2 }).await.unwrap();
+
+        assert_eq!(first.executed().len(), 1);
+        assert!(second.executed().is_empty());
+        assert_eq!(registry.backend_names(), ["fake", "fake"]);
+    }
+
+    struct ScreenOnly;
+
+    impl ActionExecutor for ScreenOnly {
+        fn name(&self) -> &'static str {
+            "screen"
+        }
+
+        fn supports(&self, kind: ActionKind) -> bool {
+            kind == ActionKind::Screenshot
+        }
+
+        fn execute<'a>(&'a self, _action: &'a Action) -> BoxFuture<'a, Result<ActionOutput, ActionError>> {
+            Box::pin(async { Ok(ActionOutput::default()) })
+        }
+    }
+
+    #[test]
+    fn screenshots_alone_do_not_control_desktop() {
+        let registry = ExecutorRegistry::default();
+        registry.register(Arc::new(ScreenOnly));
+        assert!(!registry.controls_desktop());
+
+        registry.register(Arc::new(FakeBackend::default()));
+        assert!(registry.controls_desktop());
+    }
+
+    #[tokio::test]
+    async fn missing_backend_is_unsupported() {
+        let registry = ExecutorRegistry::default();
+        let error = registry.run(&Action::KeyPress { keys: "ctrl+c".into() }).await.unwrap_err();
+        assert!(matches!(error, ActionError::Unsupported(_)));
+    }
+
+    #[test]
+    fn results_carry_status() {
+        assert_eq!(to_result("a", Ok(ActionOutput::default())).status, ActionStatus::Done);
+        assert_eq!(to_result("a", Err(ActionError::Cancelled)).status, ActionStatus::Cancelled);
+        let failed = to_result("a", Err(ActionError::Failed("boom".into())));
+        assert_eq!((failed.status, failed.error.as_deref()), (ActionStatus::Failed, Some("boom")));
+    }
+}
@@ -0,0 +1,134 @@
+// Бекенд через desktop_env: долгоживущий `python3 main.py serve`, один JSON-запрос на строку
+use futures_util::future::BoxFuture;
+use std::path::PathBuf;
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::Mutex;
+
+use super::{Action, ActionError, ActionExecutor, ActionKind, ActionOutput};
+use crate::agent::protocol::{ActionResult, ActionStatus};
+use crate::logging;
+
+struct BridgeProcess {
+    _child: Child,
+    stdin: ChildStdin,
+    stdout: Lines<BufReader<ChildStdout>>,
+}
+
+pub struct PythonBackend {
+    script: PathBuf,
+    process: Mutex<Option<BridgeProcess>>,
+}
+
+impl PythonBackend {
+    pub fn new(script: PathBuf) -> Self {
+        Self { script, process: Mutex::new(None) }
+    }
+
+    /// Подключается, только если `main.py test` сообщает, что desktop_env импортируется
+    pub fn detect(script: PathBuf) -> Option<Self> {
+        let output = std::process::Command::new("python3").arg(&script).arg("test").output().ok()?;
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !stderr.trim().is_empty() {
+            log::debug!(target: "python", "{}", stderr.trim());
+        }
+        let probe: serde_json::Value = serde_json::from_slice(&output.stdout).ok()?;
+        if probe.get("desktop_env_available").and_then(|v| v.as_bool()) == Some(true) {
+            Some(Self::new(script))
+        } else {
+            log::info!("desktop_env is not importable, python backend disabled");
+            None
+        }
+    }
+
+    fn spawn(&self) -> Result<BridgeProcess, String> {
+        let mut child = Command::new("python3")
+            .arg(&self.script)
+            .arg("serve")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("Failed to start Python: {}", e))?;
+        let stdin = child.stdin.take().ok_or("Python stdin is not available")?;
+        let stdout = child.stdout.take().ok_or("Python stdout is not available")?;
+        if let Some(stderr) = child.stderr.take() {
+            logging::forward_stderr_async("python", stderr);
+        }
+        Ok(BridgeProcess { _child: child, stdin, stdout: BufReader::new(stdout).lines() })
+    }
+
+    /// Выполняет действие через desktop_env. Если процесс умер до отправки запроса, он перезапускается
+    /// один раз; после отправки повтора нет: type_text или click не должны выполниться дважды.
+    async fn request(&self, action: &Action) -> Result<ActionOutput, ActionError> {
+        let request = serde_json::to_string(action).map_err(|e| ActionError::InvalidParams(e.to_string()))? + "\n";
+
+        let mut process = self.process.lock().await;
+        for attempt in 0..2 {
+            // Процесс на время запроса забирается из слота: если future бросят посреди обмена
+            // (аварийная остановка), процесс убивается вместе с ним и ответы не разъедутся
+            let mut bridge = match process.take() {
+                Some(bridge) => bridge,
+                None => self.spawn().map_err(ActionError::Failed)?,
+            };
+
+            match roundtrip(&mut bridge, &request).await {
+                Ok(result) => {
+                    *process = Some(bridge);
+                    return match result.status {
+                        ActionStatus::Done => Ok(ActionOutput { data: result.data, meta: result.meta }),
+                        _ => Err(ActionError::Failed(result.error.unwrap_or_else(|| "Action failed".into()))),
+                    };
+                }
+                Err(BridgeError::NotDelivered(e)) => {
+                    log::warn!("python bridge is gone (attempt {}): {}", attempt + 1, e)
+                }
+                Err(BridgeError::AfterDelivery(e)) => {
+                    log::warn!("python bridge failed after the request was sent: {}", e);
+                    return Err(ActionError::Failed(format!("Python bridge failed: {}", e)));
+                }
+            }
+        }
+        Err(ActionError::Failed("Python bridge is not responding".into()))
+    }
+}
+
+impl ActionExecutor for PythonBackend {
+    fn name(&self) -> &'static str {
+        "desktop_env"
+    }
+
+    fn supports(&self, kind: ActionKind) -> bool {
+        matches!(
+            kind,
+            ActionKind::Screenshot | ActionKind::Accessibility | ActionKind::TypeText | ActionKind::ExecuteAction
+        )
+    }
+
+    fn execute<'a>(&'a self, action: &'a Action) -> BoxFuture<'a, Result<ActionOutput, ActionError>> {
+        Box::pin(self.request(action))
+    }
+}
+
+enum BridgeError {
+    /// Запрос не записан: процесс умер раньше, действие точно не выполнялось
+    NotDelivered(String),
+    /// Запрос ушёл в процесс; действие могло выполниться
+    AfterDelivery(String),
+}
+
+async fn roundtrip(bridge: &mut BridgeProcess, request: &str) -> Result<ActionResult, BridgeError> {
+    let not_delivered = |e: std::io::Error| BridgeError::NotDelivered(e.to_string());
+    bridge.stdin.write_all(request.as_bytes()).await.map_err(not_delivered)?;
+    bridge.stdin.flush().await.map_err(not_delivered)?;
+
+    let line = bridge
+        .stdout
+        .next_line()
+        .await
+        .map_err(|e| BridgeError::AfterDelivery(e.to_string()))?
+        .ok_or_else(|| BridgeError::AfterDelivery("Python process exited".into()))?;
+    serde_json::from_str(&line).map_err(|e| BridgeError::AfterDelivery(format!("Invalid response from Python: {}", e)))
+}
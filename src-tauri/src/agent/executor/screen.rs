@@ -0,0 +1,95 @@
+// Нативный снимок экрана: кодирование в PNG/WebP и загрузка по presigned URL или inline base64
+use base64::Engine;
+use futures_util::future::BoxFuture;
+
+use super::{Action, ActionError, ActionExecutor, ActionKind, ActionOutput, ScreenshotParams};
+use crate::capture::{self, CaptureOptions, CaptureTarget};
+
+/// Больше этого в task.result не кладём: сервер должен выдать upload_url
+const INLINE_LIMIT: usize = 8 * 1024 * 1024;
+
+#[derive(Default)]
+pub struct ScreenBackend {
+    http: reqwest::Client,
+}
+
+impl ScreenBackend {
+    async fn screenshot(&self, params: &ScreenshotParams) -> Result<ActionOutput, ActionError> {
+        let options = CaptureOptions {
+            target: match (params.region, params.monitor) {
+                (Some(region), _) => CaptureTarget::Region(region),
+                (None, Some(index)) => CaptureTarget::Monitor(index),
+                (None, None) => CaptureTarget::AllMonitors,
+            },
+            format: params.format,
+            max_width: params.max_width,
+            max_height: params.max_height,
+        };
+        let shot = tauri::async_runtime::spawn_blocking(move || capture::capture(&options))
+            .await
+            .map_err(|e| ActionError::Failed(e.to_string()))?
+            .map_err(|e| ActionError::Failed(e.to_string()))?;
+
+        let mut meta = serde_json::json!({
+            "width": shot.width,
+            "height": shot.height,
+            "format": shot.format,
+            "size": shot.bytes.len(),
+            "monitor": shot.monitor,
+        });
+
+        let data = match &params.upload_url {
+            Some(url) => {
+                self.upload(url, shot.format.mime_type(), shot.bytes).await?;
+                meta["uploaded"] = true.into();
+                None
+            }
+            None if shot.bytes.len() > INLINE_LIMIT => {
+                return Err(ActionError::Failed(format!(
+                    "Screenshot is {} bytes, too large to inline; provide upload_url or max_width",
+                    shot.bytes.len()
+                )));
+            }
+            None => Some(serde_json::json!({
+                "mime_type": shot.format.mime_type(),
+                "base64": base64::engine::general_purpose::STANDARD.encode(&shot.bytes),
+            })),
+        };
+        Ok(ActionOutput { data, meta: Some(meta) })
+    }
+
+    async fn upload(&self, url: &str, mime_type: &str, bytes: Vec<u8>) -> Result<(), ActionError> {
+        let response = self
+            .http
+            .put(url)
+            .header(reqwest::header::CONTENT_TYPE, mime_type)
+            .body(bytes)
+            .send()
+            .await
+            .map_err(|e| ActionError::Failed(format!("Screenshot upload failed: {}", e)))?;
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(ActionError::Failed(format!("Screenshot upload failed with status {}", response.status())))
+        }
+    }
+}
+
+impl ActionExecutor for ScreenBackend {
+    fn name(&self) -> &'static str {
+        "screen"
+    }
+
+    fn supports(&self, kind: ActionKind) -> bool {
+        kind == ActionKind::Screenshot
+    }
+
+    fn execute<'a>(&'a self, action: &'a Action) -> BoxFuture<'a, Result<ActionOutput, ActionError>> {
+        Box::pin(async move {
+            match action {
+                Action::Screenshot(params) => self.screenshot(params).await,
+                _ => Err(ActionError::Unsupported(format!("{:?} is not supported by the screen backend", action.kind()))),
+            }
+        })
+    }
+}
@@ -0,0 +1,244 @@
+// Долгоживущий агент устройства: запускается один раз и живёт в managed state
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
+
+use crate::skygen_client::SkygenState;
+use backoff::Backoff;
+use connection::{ConnectionError, DriverEvent, KillSwitch, TaskRunner};
+
+pub mod audit;
+pub mod backoff;
+pub mod connection;
+pub mod dispatcher;
+pub mod executor;
+pub mod policy;
+pub mod protocol;
+
+pub const STATE_EVENT: &str = "agent://state";
+pub const CONNECTION_EVENT: &str = "agent://connection";
+pub const EMERGENCY_STOP_EVENT: &str = "agent://emergency-stop";
+pub const TASK_EVENT: &str = "agent://task";
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum AgentState {
+    Stopped,
+    Starting,
+    Running,
+    Reconnecting { attempt: u32 },
+    Stopping,
+    Failed { error: String },
+}
+
+impl AgentState {
+    pub fn is_active(&self) -> bool {
+        matches!(self, AgentState::Starting | AgentState::Running | AgentState::Reconnecting { .. })
+    }
+}
+
+/// Состояние сокета для UI: отдельный поток событий от жизненного цикла агента
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum ConnectionState {
+    Connecting,
+    Connected,
+    Backoff { attempt: u32, delay_ms: u64 },
+    Offline {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        reason: Option<String>,
+    },
+}
+
+pub struct Agent {
+    state: watch::Sender<AgentState>,
+    connection: watch::Sender<ConnectionState>,
+    /// Идентификатор выполняемой задачи
+    task: watch::Sender<Option<String>>,
+    cancel: Mutex<Option<CancellationToken>>,
+    kill: Arc<KillSwitch>,
+}
+
+impl Default for Agent {
+    fn default() -> Self {
+        Self {
+            state: watch::Sender::new(AgentState::Stopped),
+            connection: watch::Sender::new(ConnectionState::Offline { reason: None }),
+            task: watch::Sender::new(None),
+            cancel: Mutex::new(None),
+            kill: Arc::default(),
+        }
+    }
+}
+
+impl Agent {
+    pub fn state(&self) -> AgentState {
+        self.state.borrow().clone()
+    }
+
+    pub fn connection_state(&self) -> ConnectionState {
+        self.connection.borrow().clone()
+    }
+
+    pub fn current_task(&self) -> Option<String> {
+        self.task.borrow().clone()
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<AgentState> {
+        self.state.subscribe()
+    }
+
+    /// Запускает агента в фоне. Повторный вызов при работающем агенте ничего не делает.
+    pub fn start(&self, app: &AppHandle, runner: Arc<dyn TaskRunner>) {
+        let mut cancel = self.cancel.lock().unwrap();
+        if self.state().is_active() {
+            return;
+        }
+
+        let token = CancellationToken::new();
+        *cancel = Some(token.clone());
+        set_state(app, &self.state, AgentState::Starting);
+
+        let channels = Channels {
+            app: app.clone(),
+            state: self.state.clone(),
+            connection: self.connection.clone(),
+            task: self.task.clone(),
+        };
+        tauri::async_runtime::spawn(supervise(channels, runner, self.kill.clone(), token));
+    }
+
+    /// Аварийная остановка: текущая задача и очередь отменяются, соединение остаётся
+    pub fn emergency_stop(&self, app: &AppHandle) {
+        log::warn!("emergency stop requested");
+        self.kill.trigger();
+        let _ = app.emit(EMERGENCY_STOP_EVENT, ());
+    }
+
+    pub fn stop(&self, app: &AppHandle) {
+        let Some(token) = self.cancel.lock().unwrap().take() else { return };
+        if !self.state().is_active() {
+            return;
+        }
+        set_state(app, &self.state, AgentState::Stopping);
+        token.cancel();
+    }
+
+    /// Ждёт, пока агент выйдет из состояния Starting, но не дольше timeout
+    pub async fn wait_started(&self, timeout: Duration) -> AgentState {
+        let mut rx = self.subscribe();
+        let _ = tokio::time::timeout(timeout, rx.wait_for(|s| *s != AgentState::Starting)).await;
+        self.state()
+    }
+}
+
+fn set_state(app: &AppHandle, state: &watch::Sender<AgentState>, new_state: AgentState) {
+    log::info!("agent state: {:?}", new_state);
+    state.send_replace(new_state.clone());
+    let _ = app.emit(STATE_EVENT, new_state);
+}
+
+#[derive(Clone)]
+struct Channels {
+    app: AppHandle,
+    state: watch::Sender<AgentState>,
+    connection: watch::Sender<ConnectionState>,
+    task: watch::Sender<Option<String>>,
+}
+
+impl Channels {
+    fn agent(&self, new_state: AgentState) {
+        set_state(&self.app, &self.state, new_state);
+    }
+
+    fn connection(&self, new_state: ConnectionState) {
+        log::info!("connection state: {:?}", new_state);
+        self.connection.send_replace(new_state.clone());
+        let _ = self.app.emit(CONNECTION_EVENT, new_state);
+    }
+
+    fn task(&self, task_id: Option<String>) {
+        self.task.send_replace(task_id.clone());
+        let _ = self.app.emit(TASK_EVENT, task_id);
+    }
+}
+
+// Переподключается после любого обрыва, пока агента не остановят или токен не отвергнут
+async fn supervise(channels: Channels, runner: Arc<dyn TaskRunner>, kill: Arc<KillSwitch>, cancel: CancellationToken) {
+    let mut backoff = Backoff::default();
+    let registered = Arc::new(AtomicBool::new(false));
+
+    let events = {
+        let channels = channels.clone();
+        let registered = registered.clone();
+        Arc::new(move |event: DriverEvent| match event {
+            DriverEvent::Registered => {
+                registered.store(true, Ordering::SeqCst);
+                channels.connection(ConnectionState::Connected);
+                channels.agent(AgentState::Running);
+            }
+            DriverEvent::TaskStarted { task_id } => {
+                log::info!("task {} started", task_id);
+                channels.task(Some(task_id));
+            }
+            DriverEvent::TaskFinished { task_id, status } => {
+                log::info!("task {} finished: {:?}", task_id, status);
+                channels.task(None);
+            }
+        })
+    };
+
+    let final_state = loop {
+        // Токен устройства берём из хранилища на каждой попытке: он мог обновиться после re-enroll
+        let device = {
+            let client = channels.app.state::<SkygenState>();
+            let client = client.lock().await;
+            client.socket_device()
+        };
+        let Some(device) = device else {
+            break AgentState::Failed { error: "Device not enrolled".into() };
+        };
+
+        channels.connection(ConnectionState::Connecting);
+        registered.store(false, Ordering::SeqCst);
+        let result = connection::run_session(&device, runner.clone(), events.clone(), kill.clone(), &cancel).await;
+        // Незавершённая задача при обрыве сессии не продолжается
+        channels.task(None);
+
+        let error = match result {
+            Ok(()) => break AgentState::Stopped,
+            Err(e @ (ConnectionError::Unauthorized(_) | ConnectionError::Rejected(_))) => {
+                log::error!("device rejected by server: {}", e);
+                break AgentState::Failed { error: e.to_string() };
+            }
+            Err(e) => e,
+        };
+
+        if registered.load(Ordering::SeqCst) {
+            backoff.reset();
+        }
+        let delay = backoff.next_delay();
+        log::warn!("agent disconnected ({}), reconnecting in {:?}", error, delay);
+        channels.connection(ConnectionState::Backoff {
+            attempt: backoff.attempt(),
+            delay_ms: delay.as_millis() as u64,
+        });
+        channels.agent(AgentState::Reconnecting { attempt: backoff.attempt() });
+
+        tokio::select! {
+            _ = cancel.cancelled() => break AgentState::Stopped,
+            _ = tokio::time::sleep(delay) => {}
+        }
+    };
+
+    let reason = match &final_state {
+        AgentState::Failed { error } => Some(error.clone()),
+        _ => None,
+    };
+    channels.connection(ConnectionState::Offline { reason });
+    channels.agent(final_state);
+}
@@ -0,0 +1,375 @@
+// Политика выполнения удалённых действий: классы действий, правила и подтверждение пользователем
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+use super::executor::{Action, ActionKind};
+
+pub const APPROVAL_EVENT: &str = "approval://request";
+/// Без ответа пользователя действие считается отклонённым
+const APPROVAL_TIMEOUT: Duration = Duration::from_secs(60);
+const RULES_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionClass {
+    ReadOnly,
+    Input,
+    Filesystem,
+}
+
+impl ActionClass {
+    pub fn of(kind: ActionKind) -> Self {
+        match kind {
+            ActionKind::Screenshot | ActionKind::Accessibility => ActionClass::ReadOnly,
+            ActionKind::TypeText
+            | ActionKind::KeyPress
+            | ActionKind::MouseMove
+            | ActionKind::Click
+            | ActionKind::Scroll => ActionClass::Input,
+            // Произвольное действие desktop_env может открыть или изменить файлы
+            ActionKind::ExecuteAction => ActionClass::Filesystem,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleDecision {
+    Allow,
+    Deny,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rule {
+    pub action: ActionKind,
+    pub decision: RuleDecision,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RulesFile {
+    version: u32,
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Allow,
+    Deny,
+    Ask,
+}
+
+/// Ответ пользователя на запрос подтверждения
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Approval {
+    AllowOnce,
+    AllowSession,
+    AlwaysAllow,
+    Deny,
+    AlwaysDeny,
+}
+
+impl Approval {
+    pub fn allows(self) -> bool {
+        matches!(self, Approval::AllowOnce | Approval::AllowSession | Approval::AlwaysAllow)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingAction {
+    pub action_type: ActionKind,
+    pub class: ActionClass,
+    pub summary: String,
+}
+
+/// Один запрос на задачу: все рискованные действия задачи подтверждаются вместе
+#[derive(Debug, Clone, Serialize)]
+pub struct ApprovalRequest {
+    pub id: String,
+    pub task_id: String,
+    pub actions: Vec<PendingAction>,
+}
+
+pub type ApprovalPrompt = Arc<dyn Fn(&ApprovalRequest) + Send + Sync>;
+
+type PendingMap = Arc<Mutex<HashMap<String, (ApprovalRequest, oneshot::Sender<Approval>)>>>;
+
+pub struct Policy {
+    path: Option<PathBuf>,
+    rules: Mutex<HashMap<ActionKind, RuleDecision>>,
+    allow_session: AtomicBool,
+    pending: PendingMap,
+    prompt: Option<ApprovalPrompt>,
+}
+
+impl Policy {
+    /// Правила читаются из файла; повреждённый файл не мешает запуску, правила просто пустые
+    pub fn load(path: PathBuf) -> Self {
+        let rules = match std::fs::read(&path) {
+            Ok(bytes) => match serde_json::from_slice::<RulesFile>(&bytes) {
+                Ok(file) => file.rules.into_iter().map(|r| (r.action, r.decision)).collect(),
+                Err(e) => {
+                    log::warn!("ignoring unreadable policy file {}: {}", path.display(), e);
+                    HashMap::new()
+                }
+            },
+            Err(_) => HashMap::new(),
+        };
+        Self {
+            path: Some(path),
+            rules: Mutex::new(rules),
+            allow_session: AtomicBool::new(false),
+            pending: Arc::default(),
+            prompt: None,
+        }
+    }
+
+    pub fn on_prompt(mut self, prompt: ApprovalPrompt) -> Self {
+        self.prompt = Some(prompt);
+        self
+    }
+
+    /// Постоянные правила важнее "разрешить всё в этой сессии": запрет остаётся запретом
+    pub fn check(&self, kind: ActionKind) -> Verdict {
+        match self.rules.lock().unwrap().get(&kind) {
+            Some(RuleDecision::Allow) => return Verdict::Allow,
+            Some(RuleDecision::Deny) => return Verdict::Deny,
+            None => {}
+        }
+        if self.allow_session.load(Ordering::SeqCst) || ActionClass::of(kind) == ActionClass::ReadOnly {
+            Verdict::Allow
+        } else {
+            Verdict::Ask
+        }
+    }
+
+    /// Сбрасывает "разрешить всё" при новом запуске агента
+    pub fn reset_session(&self) {
+        self.allow_session.store(false, Ordering::SeqCst);
+    }
+
+    pub fn rules(&self) -> Vec<Rule> {
+        let mut rules: Vec<Rule> = self
+            .rules
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(action, decision)| Rule { action: *action, decision: *decision })
+            .collect();
+        rules.sort_by_key(|r| ActionKind::ALL.iter().position(|k| *k == r.action));
+        rules
+    }
+
+    /// None удаляет правило, и действие снова решается по классу
+    pub fn set_rule(&self, action: ActionKind, decision: Option<RuleDecision>) -> std::io::Result<()> {
+        {
+            let mut rules = self.rules.lock().unwrap();
+            match decision {
+                Some(decision) => rules.insert(action, decision),
+                None => rules.remove(&action),
+            };
+        }
+        self.save()
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        let Some(path) = &self.path else { return Ok(()) };
+        let file = RulesFile { version: RULES_VERSION, rules: self.rules() };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(&file)?)?;
+        std::fs::rename(&tmp, path)
+    }
+
+    pub fn pending(&self) -> Vec<ApprovalRequest> {
+        self.pending.lock().unwrap().values().map(|(request, _)| request.clone()).collect()
+    }
+
+    pub fn respond(&self, id: &str, approval: Approval) -> bool {
+        match self.pending.lock().unwrap().remove(id) {
+            Some((_, reply)) => reply.send(approval).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Спрашивает пользователя и применяет ответ к сессии и постоянным правилам.
+    /// Без подключённого окна подтверждения рискованные действия отклоняются.
+    pub async fn request_approval(&self, task_id: &str, actions: &[&Action]) -> Approval {
+        let Some(prompt) = &self.prompt else { return Approval::Deny };
+
+        let request = ApprovalRequest {
+            id: uuid::Uuid::new_v4().to_string(),
+            task_id: task_id.to_string(),
+            actions: actions
+                .iter()
+                .map(|a| PendingAction { action_type: a.kind(), class: ActionClass::of(a.kind()), summary: describe(a) })
+                .collect(),
+        };
+        let (reply, answer) = oneshot::channel();
+        self.pending.lock().unwrap().insert(request.id.clone(), (request.clone(), reply));
+        // Задачу могут отменить, пока пользователь думает: запрос не должен остаться висеть в окне
+        let _guard = PendingGuard { pending: self.pending.clone(), id: request.id.clone() };
+        prompt(&request);
+
+        let approval = match tokio::time::timeout(APPROVAL_TIMEOUT, answer).await {
+            Ok(Ok(approval)) => approval,
+            _ => {
+                log::info!("approval {} for task {} timed out", request.id, task_id);
+                Approval::Deny
+            }
+        };
+
+        match approval {
+            Approval::AllowSession => self.allow_session.store(true, Ordering::SeqCst),
+            Approval::AlwaysAllow | Approval::AlwaysDeny => {
+                let decision = if approval.allows() { RuleDecision::Allow } else { RuleDecision::Deny };
+                for action in actions {
+                    if let Err(e) = self.set_rule(action.kind(), Some(decision)) {
+                        log::warn!("failed to save policy rules: {}", e);
+                    }
+                }
+            }
+            Approval::AllowOnce | Approval::Deny => {}
+        }
+        approval
+    }
+}
+
+struct PendingGuard {
+    pending: PendingMap,
+    id: String,
+}
+
+impl Drop for PendingGuard {
+    fn drop(&mut self) {
+        self.pending.lock().unwrap().remove(&self.id);
+    }
+}
+
+/// Короткое описание для окна подтверждения; текст показывается, чтобы было видно, что именно напечатают
+fn describe(action: &Action) -> String {
+    match action {
+        Action::Screenshot(_) => "Take a screenshot".into(),
+        Action::Accessibility {} => "Read the accessibility tree".into(),
+        Action::TypeText { text } => format!("Type \"{}\"", truncate(text, 80)),
+        Action::KeyPress { keys } => format!("Press {}", keys),
+        Action::MouseMove { x, y } => format!("Move the mouse to ({}, {})", x, y),
+        Action::Click { x: Some(x), y: Some(y), button, .. } => format!("{:?} click at ({}, {})", button, x, y),
+        Action::Click { button, .. } => format!("{:?} click", button),
+        Action::Scroll { dx, dy } => format!("Scroll by ({}, {})", dx, dy),
+        Action::DesktopEnv { action } => format!("Run desktop action {}", truncate(&action.to_string(), 80)),
+    }
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_rules() -> PathBuf {
+        std::env::temp_dir().join(format!("skygen-policy-{}", uuid::Uuid::new_v4())).join("policy.json")
+    }
+
+    #[test]
+    fn classifies_every_action_kind() {
+        let classes: Vec<ActionClass> = ActionKind::ALL.iter().map(|k| ActionClass::of(*k)).collect();
+        use ActionClass::*;
+        assert_eq!(classes, [ReadOnly, ReadOnly, Input, Input, Input, Input, Input, Filesystem]);
+    }
+
+    #[test]
+    fn read_only_is_allowed_and_the_rest_asks() {
+        let policy = Policy::load(temp_rules());
+        for kind in ActionKind::ALL {
+            let expected = if ActionClass::of(*kind) == ActionClass::ReadOnly { Verdict::Allow } else { Verdict::Ask };
+            assert_eq!(policy.check(*kind), expected, "{:?}", kind);
+        }
+    }
+
+    #[test]
+    fn session_allow_does_not_override_deny_rule() {
+        let path = temp_rules();
+        let policy = Policy::load(path.clone());
+        policy.set_rule(ActionKind::Click, Some(RuleDecision::Deny)).unwrap();
+        policy.allow_session.store(true, Ordering::SeqCst);
+
+        assert_eq!(policy.check(ActionKind::Click), Verdict::Deny);
+        assert_eq!(policy.check(ActionKind::TypeText), Verdict::Allow);
+
+        policy.reset_session();
+        assert_eq!(policy.check(ActionKind::TypeText), Verdict::Ask);
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn rules_survive_reload_and_can_be_removed() {
+        let path = temp_rules();
+        let policy = Policy::load(path.clone());
+        policy.set_rule(ActionKind::Screenshot, Some(RuleDecision::Deny)).unwrap();
+        policy.set_rule(ActionKind::TypeText, Some(RuleDecision::Allow)).unwrap();
+
+        let reloaded = Policy::load(path.clone());
+        assert_eq!(reloaded.check(ActionKind::Screenshot), Verdict::Deny);
+        assert_eq!(reloaded.check(ActionKind::TypeText), Verdict::Allow);
+        assert_eq!(reloaded.rules()[0].action, ActionKind::Screenshot);
+
+        reloaded.set_rule(ActionKind::Screenshot, None).unwrap();
+        let cleared = Policy::load(path.clone());
+        assert_eq!(cleared.check(ActionKind::Screenshot), Verdict::Allow);
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn unreadable_rules_file_means_no_rules() {
+        let path = temp_rules();
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, b"{ not json").unwrap();
+
+        assert!(Policy::load(path.clone()).rules().is_empty());
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[tokio::test]
+    async fn without_prompt_risky_actions_are_denied() {
+        let policy = Policy::load(temp_rules());
+        let action = Action::TypeText { text: "rm -rf /".into() };
+        assert_eq!(policy.request_approval("t", &[&action]).await, Approval::Deny);
+    }
+
+    #[tokio::test]
+    async fn always_deny_answer_becomes_a_rule() {
+        let (ids, requests) = std::sync::mpsc::channel();
+        let path = temp_rules();
+        let policy = Policy::load(path.clone()).on_prompt(Arc::new(move |request: &ApprovalRequest| {
+            ids.send(request.clone()).unwrap();
+        }));
+        let action = Action::Click { x: Some(1), y: Some(2), button: Default::default(), count: 1 };
+
+        let answer = async {
+            let request = tokio::task::spawn_blocking(move || requests.recv().unwrap()).await.unwrap();
+            assert_eq!(request.actions[0].class, ActionClass::Input);
+            assert_eq!(request.actions[0].summary, "Left click at (1, 2)");
+            assert!(policy.respond(&request.id, Approval::AlwaysDeny));
+        };
+        let actions = [&action];
+        let (approval, ()) = tokio::join!(policy.request_approval("t", &actions), answer);
+
+        assert_eq!(approval, Approval::AlwaysDeny);
+        assert_eq!(policy.check(ActionKind::Click), Verdict::Deny);
+        assert!(policy.pending().is_empty());
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+}
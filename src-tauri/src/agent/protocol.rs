@@ -0,0 +1,138 @@
+// Сообщения WebSocket-протокола устройства (register / heartbeat / task.exec / task.result)
+use serde::{Deserialize, Serialize};
+
+use crate::skygen_client::string_or_number;
+
+/// Возможности, которые устройство объявляет при регистрации
+pub const CAPABILITIES: &[&str] = &["screenshot", "accessibility", "input", "filesystem"];
+
+/// Сообщения от устройства к серверу
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ClientMessage {
+    #[serde(rename = "register")]
+    Register {
+        device_id: String,
+        device_token: String,
+        capabilities: Vec<String>,
+    },
+    #[serde(rename = "heartbeat")]
+    Heartbeat { device_id: String, timestamp: String },
+    #[serde(rename = "task.result")]
+    TaskResult {
+        task_id: String,
+        status: TaskStatus,
+        results: Vec<ActionResult>,
+        timestamp: String,
+    },
+}
+
+impl ClientMessage {
+    pub fn register(device_id: &str, device_token: &str) -> Self {
+        ClientMessage::Register {
+            device_id: device_id.to_string(),
+            device_token: device_token.to_string(),
+            capabilities: CAPABILITIES.iter().map(|c| c.to_string()).collect(),
+        }
+    }
+
+    pub fn heartbeat(device_id: &str) -> Self {
+        ClientMessage::Heartbeat { device_id: device_id.to_string(), timestamp: now() }
+    }
+
+    pub fn task_result(task_id: &str, status: TaskStatus, results: Vec<ActionResult>) -> Self {
+        ClientMessage::TaskResult { task_id: task_id.to_string(), status, results, timestamp: now() }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("protocol messages are always serializable")
+    }
+}
+
+/// Сообщения от сервера к устройству. Неизвестные типы не считаются ошибкой.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type")]
+pub enum ServerMessage {
+    #[serde(rename = "register.ok")]
+    RegisterOk,
+    #[serde(rename = "task.exec")]
+    TaskExec(TaskExec),
+    #[serde(other)]
+    Unknown,
+}
+
+impl ServerMessage {
+    pub fn parse(text: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(text)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskExec {
+    #[serde(deserialize_with = "string_or_number")]
+    pub task_id: String,
+    #[serde(default)]
+    pub actions: Vec<ActionSpec>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActionSpec {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action_id: Option<String>,
+    #[serde(rename = "type")]
+    pub action_type: String,
+    #[serde(default)]
+    pub params: serde_json::Value,
+}
+
+/// Итог задачи целиком: cancelled — пользователь нажал аварийную остановку
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskStatus {
+    Completed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ActionStatus {
+    Done,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActionResult {
+    pub action_id: String,
+    pub status: ActionStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ActionResult {
+    pub fn done(action_id: impl Into<String>) -> Self {
+        Self { action_id: action_id.into(), status: ActionStatus::Done, data: None, meta: None, error: None }
+    }
+
+    pub fn failed(action_id: impl Into<String>, error: impl Into<String>) -> Self {
+        Self {
+            action_id: action_id.into(),
+            status: ActionStatus::Failed,
+            data: None,
+            meta: None,
+            error: Some(error.into()),
+        }
+    }
+
+    pub fn cancelled(action_id: impl Into<String>) -> Self {
+        Self { action_id: action_id.into(), status: ActionStatus::Cancelled, data: None, meta: None, error: None }
+    }
+}
+
+fn now() -> String {
+    chrono::Utc::now().to_rfc3339()
+}
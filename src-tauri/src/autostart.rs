@@ -0,0 +1,34 @@
+// Запуск при входе в систему (XDG autostart, LaunchAgent, реестр Windows) и фоновый режим без главного окна
+use tauri::plugin::TauriPlugin;
+use tauri::{AppHandle, Wry};
+use tauri_plugin_autostart::{MacosLauncher, ManagerExt};
+
+use crate::error::AppError;
+
+/// Флаг запуска без главного окна: только трей и агент
+pub const BACKGROUND_ARG: &str = "--background";
+
+/// Автозапуск всегда идёт в фоновом режиме: после перезагрузки машина агента не должна открывать окна
+pub fn plugin() -> TauriPlugin<Wry> {
+    tauri_plugin_autostart::init(MacosLauncher::LaunchAgent, Some(vec![BACKGROUND_ARG]))
+}
+
+pub fn in_background() -> bool {
+    std::env::args().any(|arg| arg == BACKGROUND_ARG)
+}
+
+/// Приводит запись автозапуска в системе к значению из настроек
+pub fn sync(app: &AppHandle, enabled: bool) -> Result<(), AppError> {
+    let launcher = app.autolaunch();
+    let current = launcher.is_enabled().map_err(|e| AppError::Autostart(e.to_string()))?;
+    if current == enabled {
+        return Ok(());
+    }
+    log::info!("autostart: {}", if enabled { "enabled" } else { "disabled" });
+    if enabled {
+        launcher.enable()
+    } else {
+        launcher.disable()
+    }
+    .map_err(|e| AppError::Autostart(e.to_string()))
+}
@@ -0,0 +1,288 @@
+// Снимки экрана: все мониторы вместе, отдельный монитор или область в глобальных координатах
+use image::imageops::FilterType;
+use image::{DynamicImage, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::Cursor;
+use xcap::Monitor;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Region {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFormat {
+    #[default]
+    Png,
+    Webp,
+}
+
+impl ImageFormat {
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "image/png",
+            ImageFormat::Webp => "image/webp",
+        }
+    }
+}
+
+/// Что снимать. Без монитора и области снимается весь рабочий стол: мониторы склеиваются по их расположению.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CaptureTarget {
+    #[default]
+    AllMonitors,
+    Monitor(usize),
+    Region(Region),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CaptureOptions {
+    pub target: CaptureTarget,
+    pub format: ImageFormat,
+    /// Уменьшение с сохранением пропорций, если снимок не влезает в эти границы
+    pub max_width: Option<u32>,
+    pub max_height: Option<u32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Capture {
+    pub bytes: Vec<u8>,
+    pub format: ImageFormat,
+    pub width: u32,
+    pub height: u32,
+    pub monitor: String,
+}
+
+#[derive(Debug)]
+pub enum CaptureError {
+    NoMonitor,
+    MonitorNotFound(usize),
+    InvalidRegion(String),
+    Capture(String),
+    Encode(String),
+}
+
+impl fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CaptureError::NoMonitor => write!(f, "No monitor available for capture"),
+            CaptureError::MonitorNotFound(index) => write!(f, "Monitor {} not found", index),
+            CaptureError::InvalidRegion(e) => write!(f, "Invalid capture region: {}", e),
+            CaptureError::Capture(e) => write!(f, "Screen capture failed: {}", e),
+            CaptureError::Encode(e) => write!(f, "Image encoding failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for CaptureError {}
+
+fn capture_err(e: impl fmt::Display) -> CaptureError {
+    CaptureError::Capture(e.to_string())
+}
+
+/// Снимает экран синхронно; из async-кода вызывать через spawn_blocking
+pub fn capture(options: &CaptureOptions) -> Result<Capture, CaptureError> {
+    let monitors = Monitor::all().map_err(capture_err)?;
+    if monitors.is_empty() {
+        return Err(CaptureError::NoMonitor);
+    }
+
+    let (monitor, image) = match options.target {
+        CaptureTarget::AllMonitors => {
+            let mut parts = Vec::with_capacity(monitors.len());
+            for monitor in &monitors {
+                parts.push((bounds(monitor)?, monitor.capture_image().map_err(capture_err)?));
+            }
+            let names: Vec<String> = monitors.iter().filter_map(|m| m.name().ok()).collect();
+            (names.join(", "), stitch(parts))
+        }
+        CaptureTarget::Monitor(index) => {
+            let monitor = monitors.get(index).ok_or(CaptureError::MonitorNotFound(index))?;
+            (monitor.name().unwrap_or_default(), monitor.capture_image().map_err(capture_err)?)
+        }
+        CaptureTarget::Region(region) => {
+            let monitor = monitors
+                .iter()
+                .find(|m| bounds(m).is_ok_and(|b| contains(&b, region.x, region.y)))
+                .ok_or_else(|| CaptureError::InvalidRegion("origin is outside of every monitor".into()))?;
+            let image = monitor.capture_image().map_err(capture_err)?;
+            (monitor.name().unwrap_or_default(), crop(&image, bounds(monitor)?, region)?)
+        }
+    };
+
+    let image = downscale(image, options.max_width, options.max_height);
+    let (width, height) = (image.width(), image.height());
+    Ok(Capture { bytes: encode(image, options.format)?, format: options.format, width, height, monitor })
+}
+
+/// Положение и размер монитора в логических координатах
+fn bounds(monitor: &Monitor) -> Result<Region, CaptureError> {
+    Ok(Region {
+        x: monitor.x().map_err(capture_err)?,
+        y: monitor.y().map_err(capture_err)?,
+        width: monitor.width().map_err(capture_err)?,
+        height: monitor.height().map_err(capture_err)?,
+    })
+}
+
+fn contains(bounds: &Region, x: i32, y: i32) -> bool {
+    x >= bounds.x && y >= bounds.y && x < bounds.x + bounds.width as i32 && y < bounds.y + bounds.height as i32
+}
+
+/// Физических пикселей на логический: снимок в физических пикселях, а координаты логические
+fn scale(bounds: &Region, image: &RgbaImage) -> f64 {
+    image.width() as f64 / bounds.width.max(1) as f64
+}
+
+// Склеивает снимки мониторов по их логическим координатам в масштабе самого плотного монитора;
+// места, не покрытые ни одним монитором, остаются прозрачными
+fn stitch(parts: Vec<(Region, RgbaImage)>) -> RgbaImage {
+    if parts.len() == 1 {
+        return parts.into_iter().next().map(|(_, image)| image).unwrap_or_default();
+    }
+    let factor = parts.iter().map(|(bounds, image)| scale(bounds, image)).fold(1.0, f64::max);
+    let left = parts.iter().map(|(b, _)| b.x).min().unwrap_or(0);
+    let top = parts.iter().map(|(b, _)| b.y).min().unwrap_or(0);
+    let right = parts.iter().map(|(b, _)| b.x + b.width as i32).max().unwrap_or(0);
+    let bottom = parts.iter().map(|(b, _)| b.y + b.height as i32).max().unwrap_or(0);
+    let physical = |logical: i32| (logical as f64 * factor).round() as u32;
+
+    let mut canvas = RgbaImage::new(physical(right - left), physical(bottom - top));
+    for (bounds, image) in parts {
+        let (width, height) = (physical(bounds.width as i32), physical(bounds.height as i32));
+        let image = if image.dimensions() == (width, height) {
+            image
+        } else {
+            image::imageops::resize(&image, width, height, FilterType::Triangle)
+        };
+        let (x, y) = (physical(bounds.x - left), physical(bounds.y - top));
+        image::imageops::replace(&mut canvas, &image, x.into(), y.into());
+    }
+    canvas
+}
+
+// Область в логических координатах пересчитывается в пиксели снимка монитора и обрезается по его краю
+fn crop(image: &RgbaImage, bounds: Region, region: Region) -> Result<RgbaImage, CaptureError> {
+    if region.width == 0 || region.height == 0 {
+        return Err(CaptureError::InvalidRegion("width and height must be positive".into()));
+    }
+    let factor = scale(&bounds, image);
+    let left = ((region.x - bounds.x).max(0) as f64 * factor) as u32;
+    let top = ((region.y - bounds.y).max(0) as f64 * factor) as u32;
+    let width = ((region.width as f64 * factor) as u32).min(image.width().saturating_sub(left));
+    let height = ((region.height as f64 * factor) as u32).min(image.height().saturating_sub(top));
+    // Область меньше физического пикселя: такой снимок не закодировать
+    if width == 0 || height == 0 {
+        return Err(CaptureError::InvalidRegion("region is smaller than one pixel".into()));
+    }
+    Ok(image::imageops::crop_imm(image, left, top, width, height).to_image())
+}
+
+fn downscale(image: RgbaImage, max_width: Option<u32>, max_height: Option<u32>) -> DynamicImage {
+    let image = DynamicImage::ImageRgba8(image);
+    let bound_width = max_width.unwrap_or(u32::MAX).max(1);
+    let bound_height = max_height.unwrap_or(u32::MAX).max(1);
+    if image.width() <= bound_width && image.height() <= bound_height {
+        return image;
+    }
+    image.resize(bound_width, bound_height, FilterType::Triangle)
+}
+
+fn encode(image: DynamicImage, format: ImageFormat) -> Result<Vec<u8>, CaptureError> {
+    let mut bytes = Vec::new();
+    let format = match format {
+        ImageFormat::Png => image::ImageFormat::Png,
+        ImageFormat::Webp => image::ImageFormat::WebP,
+    };
+    image
+        .write_to(&mut Cursor::new(&mut bytes), format)
+        .map_err(|e| CaptureError::Encode(e.to_string()))?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    fn region(x: i32, y: i32, width: u32, height: u32) -> Region {
+        Region { x, y, width, height }
+    }
+
+    fn filled(width: u32, height: u32, value: u8) -> RgbaImage {
+        RgbaImage::from_pixel(width, height, Rgba([value, value, value, 255]))
+    }
+
+    #[test]
+    fn downscale_keeps_small_images_and_aspect_ratio() {
+        assert_eq!(downscale(filled(800, 600, 0), Some(1024), None).width(), 800);
+        let image = downscale(filled(2000, 1000, 0), Some(1000), Some(1000));
+        assert_eq!((image.width(), image.height()), (1000, 500));
+        let image = downscale(filled(2000, 1000, 0), None, Some(250));
+        assert_eq!((image.width(), image.height()), (500, 250));
+    }
+
+    #[test]
+    fn crop_scales_logical_region_to_pixels() {
+        // Монитор 100x50 логических точек с масштабом 2 начинается с x = 100
+        let image = filled(200, 100, 0);
+        let cropped = crop(&image, region(100, 0, 100, 50), region(110, 5, 20, 10)).unwrap();
+        assert_eq!(cropped.dimensions(), (40, 20));
+    }
+
+    #[test]
+    fn crop_is_clipped_by_monitor_edge() {
+        let image = filled(100, 100, 0);
+        let cropped = crop(&image, region(0, 0, 100, 100), region(90, 95, 50, 50)).unwrap();
+        assert_eq!(cropped.dimensions(), (10, 5));
+    }
+
+    #[test]
+    fn crop_rejects_empty_regions() {
+        let image = filled(100, 100, 0);
+        let empty = crop(&image, region(0, 0, 100, 100), region(0, 0, 0, 10));
+        assert!(matches!(empty, Err(CaptureError::InvalidRegion(_))));
+        // Монитор 200 логических точек в снимке на 100 пикселей: точка — это полпикселя
+        let image = filled(100, 100, 0);
+        let tiny = crop(&image, region(0, 0, 200, 200), region(10, 10, 1, 1));
+        assert!(matches!(tiny, Err(CaptureError::InvalidRegion(e)) if e.contains("pixel")));
+    }
+
+    #[test]
+    fn stitch_places_monitors_by_logical_position() {
+        // Слева монитор 100x50 с масштабом 2, справа 100x100 с масштабом 1, выше на 50 точек
+        let left = (region(0, 50, 100, 50), filled(200, 100, 10));
+        let right = (region(100, 0, 100, 100), filled(100, 100, 20));
+        let image = stitch(vec![left, right]);
+
+        assert_eq!(image.dimensions(), (400, 200));
+        assert_eq!(image.get_pixel(0, 199), &Rgba([10, 10, 10, 255]));
+        assert_eq!(image.get_pixel(399, 0), &Rgba([20, 20, 20, 255]));
+        // Над левым монитором экрана нет
+        assert_eq!(image.get_pixel(0, 0), &Rgba([0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn single_monitor_is_not_resampled() {
+        let image = stitch(vec![(region(-1920, 0, 1920, 1080), filled(3840, 2160, 5))]);
+        assert_eq!(image.dimensions(), (3840, 2160));
+    }
+
+    #[test]
+    fn encode_writes_requested_format() {
+        let png = encode(DynamicImage::ImageRgba8(filled(30, 20, 7)), ImageFormat::Png).unwrap();
+        assert!(png.starts_with(b"\x89PNG"));
+        let webp = encode(DynamicImage::ImageRgba8(filled(30, 20, 7)), ImageFormat::Webp).unwrap();
+        assert_eq!((&webp[..4], &webp[8..12]), (&b"RIFF"[..], &b"WEBP"[..]));
+
+        for bytes in [png, webp] {
+            let decoded = image::load_from_memory(&bytes).unwrap();
+            assert_eq!((decoded.width(), decoded.height()), (30, 20));
+        }
+    }
+}
@@ -0,0 +1,239 @@
+// Зашифрованный файл для машин без keyring (headless Linux, CI-агенты)
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use super::{CredentialError, CredentialStore, StoredCredentials};
+
+const FORMAT_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct EncryptedFile {
+    version: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+pub struct EncryptedFileStore {
+    path: PathBuf,
+}
+
+impl EncryptedFileStore {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    fn key_file(&self) -> PathBuf {
+        self.path.with_extension("key")
+    }
+
+    fn read_key(&self) -> Result<Option<Vec<u8>>, CredentialError> {
+        match fs::read(self.key_file()) {
+            Ok(key) => Ok(Some(key)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Случайный ключ рядом с файлом (права 0600); создаётся при первой записи
+    fn key(&self) -> Result<Vec<u8>, CredentialError> {
+        if let Some(key) = self.read_key()? {
+            return Ok(key);
+        }
+        let mut key = vec![0u8; 32];
+        rand::thread_rng().fill_bytes(&mut key);
+        write_private(&self.key_file(), &key)?;
+        Ok(key)
+    }
+}
+
+fn cipher(secret: &[u8], salt: &[u8]) -> Aes256Gcm {
+    let mut hasher = Sha256::new();
+    hasher.update(b"skygen-credentials-v1");
+    hasher.update(secret);
+    hasher.update(salt);
+    let key = hasher.finalize();
+    Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key))
+}
+
+impl CredentialStore for EncryptedFileStore {
+    fn name(&self) -> &'static str {
+        "encrypted-file"
+    }
+
+    fn load(&self) -> Result<Option<StoredCredentials>, CredentialError> {
+        let raw = match fs::read(&self.path) {
+            Ok(raw) => raw,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let file: EncryptedFile = serde_json::from_slice(&raw).map_err(|e| CredentialError::Format(e.to_string()))?;
+        if file.version != FORMAT_VERSION {
+            return Err(CredentialError::Format(format!("unsupported version {}", file.version)));
+        }
+
+        let decode = |s: &str| BASE64.decode(s).map_err(|e| CredentialError::Format(e.to_string()));
+        let salt = decode(&file.salt)?;
+        let nonce = decode(&file.nonce)?;
+        let ciphertext = decode(&file.ciphertext)?;
+        if nonce.len() != 12 {
+            return Err(CredentialError::Format("invalid nonce".into()));
+        }
+
+        let key = self.read_key()?.ok_or_else(|| CredentialError::Crypto("key file is missing".into()))?;
+        let plaintext = cipher(&key, &salt)
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())
+            .map_err(|_| CredentialError::Crypto("decryption failed".into()))?;
+        parse(&plaintext).map(Some)
+    }
+
+    fn save(&self, credentials: &StoredCredentials) -> Result<(), CredentialError> {
+        let mut salt = [0u8; 16];
+        let mut nonce = [0u8; 12];
+        rand::thread_rng().fill_bytes(&mut salt);
+        rand::thread_rng().fill_bytes(&mut nonce);
+
+        let plaintext = serde_json::to_vec(credentials).map_err(|e| CredentialError::Format(e.to_string()))?;
+        let ciphertext = cipher(&self.key()?, &salt)
+            .encrypt(Nonce::from_slice(&nonce), plaintext.as_ref())
+            .map_err(|_| CredentialError::Crypto("encryption failed".into()))?;
+
+        let file = EncryptedFile {
+            version: FORMAT_VERSION,
+            salt: BASE64.encode(salt),
+            nonce: BASE64.encode(nonce),
+            ciphertext: BASE64.encode(ciphertext),
+        };
+        let json = serde_json::to_vec(&file).map_err(|e| CredentialError::Format(e.to_string()))?;
+        write_private(&self.path, &json)
+    }
+
+    fn clear(&self) -> Result<(), CredentialError> {
+        match fs::remove_file(&self.path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+fn parse(plaintext: &[u8]) -> Result<StoredCredentials, CredentialError> {
+    serde_json::from_slice(plaintext).map_err(|e| CredentialError::Format(e.to_string()))
+}
+
+// Пишем через временный файл, чтобы не оставить половину записи при падении.
+// Файл сразу создаётся с правами 0600: между созданием и chmod секрет был бы виден остальным.
+fn write_private(path: &Path, contents: &[u8]) -> Result<(), CredentialError> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    // Остаток прерванной записи: create_new на нём не сработает
+    let _ = fs::remove_file(&tmp);
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(&tmp)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    drop(file);
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_store() -> EncryptedFileStore {
+        EncryptedFileStore::new(std::env::temp_dir().join(format!("skygen-credentials-{}", uuid::Uuid::new_v4())).join("credentials.enc"))
+    }
+
+    fn cleanup(store: &EncryptedFileStore) {
+        let _ = fs::remove_dir_all(store.path.parent().unwrap());
+    }
+
+    fn credentials() -> StoredCredentials {
+        StoredCredentials { access_token: Some("access".into()), refresh_token: Some("refresh".into()), device: None }
+    }
+
+    #[test]
+    fn saved_credentials_load_back() {
+        let store = temp_store();
+        assert!(store.load().unwrap().is_none());
+        store.save(&credentials()).unwrap();
+
+        let loaded = store.load().unwrap().unwrap();
+        assert_eq!(loaded.access_token.as_deref(), Some("access"));
+        assert_eq!(loaded.refresh_token.as_deref(), Some("refresh"));
+        assert!(!fs::read_to_string(&store.path).unwrap().contains("refresh"));
+
+        store.clear().unwrap();
+        assert!(store.load().unwrap().is_none());
+        cleanup(&store);
+    }
+
+    #[test]
+    fn missing_key_file_is_a_crypto_error() {
+        let store = temp_store();
+        store.save(&credentials()).unwrap();
+        fs::remove_file(store.key_file()).unwrap();
+        assert!(matches!(store.load(), Err(CredentialError::Crypto(_))));
+        cleanup(&store);
+    }
+
+    #[test]
+    fn wrong_key_is_a_crypto_error() {
+        let store = temp_store();
+        store.save(&credentials()).unwrap();
+        fs::write(store.key_file(), [7u8; 32]).unwrap();
+        assert!(matches!(store.load(), Err(CredentialError::Crypto(_))));
+        cleanup(&store);
+    }
+
+    #[test]
+    fn unsupported_version_is_a_format_error() {
+        let store = temp_store();
+        store.save(&credentials()).unwrap();
+        let mut file: serde_json::Value = serde_json::from_slice(&fs::read(&store.path).unwrap()).unwrap();
+        file["version"] = 2.into();
+        fs::write(&store.path, file.to_string()).unwrap();
+        assert!(matches!(store.load(), Err(CredentialError::Format(e)) if e.contains("version 2")));
+        cleanup(&store);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn files_are_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let store = temp_store();
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        store.save(&credentials()).unwrap();
+        assert_eq!(mode(&store.path), 0o600);
+        assert_eq!(mode(&store.key_file()), 0o600);
+
+        // Временный файл создаётся сразу закрытым, даже поверх оставшегося от прерванной записи
+        let tmp = store.path.with_file_name("probe.tmp");
+        fs::write(&tmp, b"stale").unwrap();
+        write_private(&store.path.with_file_name("probe"), b"secret").unwrap();
+        assert_eq!(mode(&store.path.with_file_name("probe")), 0o600);
+        assert!(!tmp.exists());
+        cleanup(&store);
+    }
+}
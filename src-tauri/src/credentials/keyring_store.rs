@@ -0,0 +1,61 @@
+// Бекенд на системном хранилище секретов (Keychain, Credential Manager, Secret Service)
+use keyring::Entry;
+
+use super::{CredentialError, CredentialStore, StoredCredentials};
+
+const SERVICE: &str = "com.skygen.desktop";
+const ACCOUNT: &str = "session";
+
+pub struct KeyringStore {
+    service: String,
+    account: String,
+}
+
+impl KeyringStore {
+    /// Отдельная запись на профиль бекенда: session-<profile>
+    pub fn for_profile(profile: &str) -> Self {
+        Self { service: SERVICE.to_string(), account: format!("{}-{}", ACCOUNT, profile) }
+    }
+
+    fn entry(&self) -> Result<Entry, CredentialError> {
+        Entry::new(&self.service, &self.account).map_err(|e| CredentialError::Keyring(e.to_string()))
+    }
+
+    /// Проверяет, что хранилище реально отвечает (на headless Linux Secret Service обычно отсутствует)
+    pub fn probe(&self) -> Result<(), CredentialError> {
+        match self.entry()?.get_password() {
+            Ok(_) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(CredentialError::Keyring(e.to_string())),
+        }
+    }
+}
+
+impl CredentialStore for KeyringStore {
+    fn name(&self) -> &'static str {
+        "keyring"
+    }
+
+    fn load(&self) -> Result<Option<StoredCredentials>, CredentialError> {
+        match self.entry()?.get_password() {
+            Ok(json) => serde_json::from_str(&json)
+                .map(Some)
+                .map_err(|e| CredentialError::Format(e.to_string())),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(CredentialError::Keyring(e.to_string())),
+        }
+    }
+
+    fn save(&self, credentials: &StoredCredentials) -> Result<(), CredentialError> {
+        let json = serde_json::to_string(credentials).map_err(|e| CredentialError::Format(e.to_string()))?;
+        self.entry()?
+            .set_password(&json)
+            .map_err(|e| CredentialError::Keyring(e.to_string()))
+    }
+
+    fn clear(&self) -> Result<(), CredentialError> {
+        match self.entry()?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(CredentialError::Keyring(e.to_string())),
+        }
+    }
+}
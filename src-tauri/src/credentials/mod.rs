@@ -0,0 +1,82 @@
+// Хранилище учётных данных: access/refresh токены и токен устройства
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::settings::ProfileId;
+use crate::skygen_client::DeviceCredentials;
+
+mod file_store;
+mod keyring_store;
+
+pub use file_store::EncryptedFileStore;
+pub use keyring_store::KeyringStore;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StoredCredentials {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub access_token: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device: Option<DeviceCredentials>,
+}
+
+#[derive(Debug)]
+pub enum CredentialError {
+    Io(std::io::Error),
+    Keyring(String),
+    Crypto(String),
+    Format(String),
+}
+
+impl fmt::Display for CredentialError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CredentialError::Io(e) => write!(f, "Credential file error: {}", e),
+            CredentialError::Keyring(e) => write!(f, "Keyring error: {}", e),
+            CredentialError::Crypto(e) => write!(f, "Credential encryption error: {}", e),
+            CredentialError::Format(e) => write!(f, "Corrupted credentials: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for CredentialError {}
+
+impl From<std::io::Error> for CredentialError {
+    fn from(e: std::io::Error) -> Self {
+        CredentialError::Io(e)
+    }
+}
+
+pub trait CredentialStore: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn load(&self) -> Result<Option<StoredCredentials>, CredentialError>;
+    fn save(&self, credentials: &StoredCredentials) -> Result<(), CredentialError>;
+    fn clear(&self) -> Result<(), CredentialError>;
+}
+
+/// Выбирает бекенд хранилища для профиля. SKYGEN_CREDENTIAL_STORE=keyring|file задаёт его явно,
+/// иначе используется системный keyring, а если он недоступен (headless Linux) — зашифрованный файл.
+pub fn open(data_dir: &Path, profile: ProfileId) -> Arc<dyn CredentialStore> {
+    let keyring = KeyringStore::for_profile(profile.name());
+    let file_store = || {
+        let path = data_dir.join(format!("credentials-{}.enc", profile.name()));
+        Arc::new(EncryptedFileStore::new(path)) as Arc<dyn CredentialStore>
+    };
+
+    match std::env::var("SKYGEN_CREDENTIAL_STORE").as_deref() {
+        Ok("file") => return file_store(),
+        Ok("keyring") => return Arc::new(keyring),
+        _ => {}
+    }
+
+    match keyring.probe() {
+        Ok(()) => Arc::new(keyring),
+        Err(e) => {
+            log::warn!("keyring unavailable, falling back to encrypted file: {}", e);
+            file_store()
+        }
+    }
+}
@@ -0,0 +1,195 @@
+// Ссылки skygen://: вход по magic link, реферальные коды и переход к задаче
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::error::AppError;
+use crate::skygen_client::SkygenState;
+
+pub const SCHEME: &str = "skygen";
+pub const AUTH_EVENT: &str = "deep-link://auth";
+pub const REFERRAL_EVENT: &str = "deep-link://referral";
+pub const TASK_EVENT: &str = "deep-link://task";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeepLink {
+    /// skygen://auth/verify?token=…
+    AuthVerify { token: String },
+    /// skygen://ref/<code>
+    Referral { code: String },
+    /// skygen://task/<id>
+    Task { id: String },
+}
+
+#[derive(Clone, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+enum AuthOutcome {
+    SignedIn,
+    Failed { error: AppError },
+}
+
+#[derive(Clone, Serialize)]
+struct ReferralPayload {
+    code: String,
+}
+
+#[derive(Clone, Serialize)]
+struct TaskPayload {
+    id: String,
+}
+
+/// Последний реферальный код: ссылка могла прийти до того, как UI подписался на события
+#[derive(Default)]
+pub struct PendingReferral(Mutex<Option<String>>);
+
+impl PendingReferral {
+    pub fn take(&self) -> Option<String> {
+        self.0.lock().unwrap().take()
+    }
+}
+
+pub fn is_link(arg: &str) -> bool {
+    arg.starts_with(&format!("{}://", SCHEME))
+}
+
+pub fn parse(link: &str) -> Result<DeepLink, AppError> {
+    let invalid = |reason: &str| AppError::InvalidDeepLink(format!("{}: {}", reason, link));
+    let url = reqwest::Url::parse(link).map_err(|_| invalid("not a url"))?;
+    if url.scheme() != SCHEME {
+        return Err(invalid("unexpected scheme"));
+    }
+    let segments: Vec<&str> = url.path_segments().map(|s| s.filter(|s| !s.is_empty()).collect()).unwrap_or_default();
+
+    match (url.host_str(), segments.as_slice()) {
+        (Some("auth"), ["verify"]) => {
+            let token = url
+                .query_pairs()
+                .find(|(key, _)| key == "token")
+                .map(|(_, value)| value.into_owned())
+                .filter(|token| !token.is_empty())
+                .ok_or_else(|| invalid("missing token"))?;
+            Ok(DeepLink::AuthVerify { token })
+        }
+        (Some("ref"), [code]) if is_identifier(code) => Ok(DeepLink::Referral { code: code.to_string() }),
+        (Some("task"), [id]) if is_identifier(id) => Ok(DeepLink::Task { id: id.to_string() }),
+        _ => Err(invalid("unknown route")),
+    }
+}
+
+// Коды и идентификаторы попадают в UI и в запросы, поэтому пропускаем только безопасные символы
+fn is_identifier(value: &str) -> bool {
+    !value.is_empty() && value.len() <= 64 && value.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Разбирает ссылку и запускает её обработчик; некорректные ссылки только логируются
+pub fn handle(app: &AppHandle, link: &str) {
+    let link = match parse(link) {
+        Ok(link) => link,
+        Err(e) => {
+            log::warn!("ignoring deep link: {}", e);
+            return;
+        }
+    };
+    // Токен в лог не пишем
+    match link {
+        DeepLink::AuthVerify { token } => {
+            log::info!("deep link: magic link sign-in");
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move { verify(&app, &token).await });
+        }
+        DeepLink::Referral { code } => {
+            log::info!("deep link: referral {}", code);
+            *app.state::<PendingReferral>().0.lock().unwrap() = Some(code.clone());
+            let _ = app.emit(REFERRAL_EVENT, ReferralPayload { code });
+            focus(app);
+        }
+        DeepLink::Task { id } => {
+            log::info!("deep link: task {}", id);
+            let _ = app.emit(TASK_EVENT, TaskPayload { id });
+            focus(app);
+        }
+    }
+}
+
+async fn verify(app: &AppHandle, token: &str) {
+    let result = app.state::<SkygenState>().lock().await.verify_magic_link(token).await;
+    let outcome = match result {
+        Ok(_) => AuthOutcome::SignedIn,
+        Err(e) => {
+            log::warn!("magic link sign-in failed: {}", e);
+            AuthOutcome::Failed { error: e.into() }
+        }
+    };
+    let _ = app.emit(AUTH_EVENT, outcome);
+    crate::tray::refresh(app);
+    focus(app);
+}
+
+// Ссылку открыли из браузера или почты: окно приложения должно оказаться перед пользователем
+fn focus(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = crate::open_main_window(&app) {
+            log::warn!("failed to open main window: {}", e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reason(link: &str) -> String {
+        match parse(link) {
+            Err(AppError::InvalidDeepLink(e)) => e.split(':').next().unwrap().to_string(),
+            other => panic!("expected invalid link, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn auth_verify_takes_token() {
+        assert_eq!(parse("skygen://auth/verify?token=abc%2B1").unwrap(), DeepLink::AuthVerify { token: "abc+1".into() });
+        assert_eq!(parse("skygen://auth/verify/?utm=mail&token=xyz").unwrap(), DeepLink::AuthVerify { token: "xyz".into() });
+    }
+
+    #[test]
+    fn auth_verify_needs_token() {
+        assert_eq!(reason("skygen://auth/verify"), "missing token");
+        assert_eq!(reason("skygen://auth/verify?token="), "missing token");
+    }
+
+    #[test]
+    fn referral_and_task_take_identifiers() {
+        assert_eq!(parse("skygen://ref/FRIEND_2024").unwrap(), DeepLink::Referral { code: "FRIEND_2024".into() });
+        assert_eq!(parse("skygen://task/5f1c-9a").unwrap(), DeepLink::Task { id: "5f1c-9a".into() });
+    }
+
+    #[test]
+    fn unsafe_identifiers_are_rejected() {
+        assert_eq!(reason("skygen://ref/a%3Cb%3E"), "unknown route");
+        assert_eq!(reason("skygen://task/a.b"), "unknown route");
+        assert_eq!(reason(&format!("skygen://ref/{}", "a".repeat(65))), "unknown route");
+        assert_eq!(reason("skygen://task/"), "unknown route");
+        assert_eq!(reason("skygen://task/a/b"), "unknown route");
+    }
+
+    #[test]
+    fn other_schemes_are_rejected() {
+        assert_eq!(reason("https://skygen.ai/ref/abc"), "unexpected scheme");
+        assert_eq!(reason("skygen-dev://ref/abc"), "unexpected scheme");
+        assert_eq!(reason("not a link"), "not a url");
+    }
+
+    #[test]
+    fn unknown_routes_are_rejected() {
+        assert_eq!(reason("skygen://settings"), "unknown route");
+        assert_eq!(reason("skygen://auth/login?token=abc"), "unknown route");
+    }
+
+    #[test]
+    fn only_own_scheme_is_a_link() {
+        assert!(is_link("skygen://ref/abc"));
+        assert!(!is_link("--flag"));
+        assert!(!is_link("skygen-dev://ref/abc"));
+    }
+}
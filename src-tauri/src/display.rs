@@ -0,0 +1,100 @@
+// Выбор монитора для оверлея и раскладка окон по мониторам
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Monitor, PhysicalPosition, PhysicalSize};
+
+use crate::error::AppError;
+
+/// На каком мониторе показывать оверлей
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum OverlayTarget {
+    /// Монитор под курсором, иначе монитор окна в фокусе
+    #[default]
+    Auto,
+    Primary,
+    Monitor { name: String },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MonitorInfo {
+    pub index: usize,
+    pub name: Option<String>,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub scale_factor: f64,
+    pub primary: bool,
+}
+
+pub fn list(app: &AppHandle) -> Result<Vec<MonitorInfo>, AppError> {
+    let primary = app.primary_monitor()?.and_then(|m| m.name().cloned());
+    Ok(app
+        .available_monitors()?
+        .iter()
+        .enumerate()
+        .map(|(index, monitor)| MonitorInfo {
+            index,
+            name: monitor.name().cloned(),
+            x: monitor.position().x,
+            y: monitor.position().y,
+            width: monitor.size().width,
+            height: monitor.size().height,
+            scale_factor: monitor.scale_factor(),
+            primary: primary.is_some() && monitor.name() == primary.as_ref(),
+        })
+        .collect())
+}
+
+pub fn all(app: &AppHandle) -> Result<Vec<Monitor>, AppError> {
+    let monitors = app.available_monitors()?;
+    if monitors.is_empty() {
+        return Err(AppError::NoMonitor);
+    }
+    Ok(monitors)
+}
+
+/// Монитор для оверлея; закреплённый, но отключённый монитор заменяется автоматическим выбором
+pub fn resolve(app: &AppHandle, target: &OverlayTarget) -> Result<Monitor, AppError> {
+    match target {
+        OverlayTarget::Primary => {}
+        OverlayTarget::Monitor { name } => {
+            if let Some(monitor) = app.available_monitors()?.into_iter().find(|m| m.name() == Some(name)) {
+                return Ok(monitor);
+            }
+            log::warn!("overlay monitor {} is not connected, falling back to auto", name);
+            return resolve(app, &OverlayTarget::Auto);
+        }
+        OverlayTarget::Auto => {
+            if let Some(monitor) = under_cursor(app).or_else(|| focused(app)) {
+                return Ok(monitor);
+            }
+        }
+    }
+    app.primary_monitor()?.ok_or(AppError::NoMonitor)
+}
+
+// На Wayland позиция курсора недоступна, тогда сюда приходит ошибка
+fn under_cursor(app: &AppHandle) -> Option<Monitor> {
+    let cursor = app.cursor_position().ok()?;
+    app.monitor_from_point(cursor.x, cursor.y).ok().flatten()
+}
+
+fn focused(app: &AppHandle) -> Option<Monitor> {
+    app.webview_windows()
+        .values()
+        .find(|window| window.is_focused().unwrap_or(false))
+        .and_then(|window| window.current_monitor().ok().flatten())
+}
+
+/// Позиция и размер окна (логические width x height) по центру монитора, в физических пикселях,
+/// чтобы на мониторах с разным масштабом окно не уезжало
+pub fn centered(monitor: &Monitor, width: f64, height: f64) -> (PhysicalPosition<i32>, PhysicalSize<u32>) {
+    let scale = monitor.scale_factor();
+    let size = PhysicalSize::new((width * scale).round() as u32, (height * scale).round() as u32);
+    let origin = monitor.position();
+    let area = monitor.size();
+    let x = origin.x + (area.width as i32 - size.width as i32) / 2;
+    let y = origin.y + (area.height as i32 - size.height as i32) / 2;
+    (PhysicalPosition::new(x, y), size)
+}
@@ -0,0 +1,243 @@
+// Единый тип ошибки для всех Tauri-команд: стабильный code для UI и локализованное сообщение
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::sync::OnceLock;
+
+use crate::credentials::CredentialError;
+use crate::skygen_client::ClientError;
+
+#[derive(Debug, Clone)]
+pub enum AppError {
+    Network(String),
+    InvalidCredentials,
+    RateLimited,
+    AccountLocked,
+    EmailInUse,
+    NotAuthenticated,
+    SessionExpired,
+    DeviceNotEnrolled,
+    Backend { status: u16, body: String },
+    InvalidResponse(String),
+    Credentials(String),
+    HelperNotRunning,
+    HelperFailed(String),
+    PythonUnavailable(String),
+    NoMonitor,
+    MonitorNotFound(String),
+    Agent(String),
+    ApprovalNotFound,
+    InvalidShortcut(String),
+    ShortcutConflict(String),
+    ShortcutUnavailable(String),
+    ShortcutNotBound,
+    InvalidSettings(String),
+    Autostart(String),
+    Updater(String),
+    InvalidMagicLink,
+    InvalidDeepLink(String),
+    LogExport(String),
+    Internal(String),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Lang {
+    En,
+    Ru,
+}
+
+fn lang() -> Lang {
+    static LANG: OnceLock<Lang> = OnceLock::new();
+    *LANG.get_or_init(|| match sys_locale::get_locale() {
+        Some(locale) if locale.to_lowercase().starts_with("ru") => Lang::Ru,
+        _ => Lang::En,
+    })
+}
+
+impl AppError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::Network(_) => "network",
+            AppError::InvalidCredentials => "invalid_credentials",
+            AppError::RateLimited => "rate_limited",
+            AppError::AccountLocked => "account_locked",
+            AppError::EmailInUse => "email_in_use",
+            AppError::NotAuthenticated => "not_authenticated",
+            AppError::SessionExpired => "session_expired",
+            AppError::DeviceNotEnrolled => "device_not_enrolled",
+            AppError::Backend { .. } => "backend",
+            AppError::InvalidResponse(_) => "invalid_response",
+            AppError::Credentials(_) => "credentials",
+            AppError::HelperNotRunning => "helper_not_running",
+            AppError::HelperFailed(_) => "helper_failed",
+            AppError::PythonUnavailable(_) => "python_unavailable",
+            AppError::NoMonitor => "no_monitor",
+            AppError::MonitorNotFound(_) => "monitor_not_found",
+            AppError::Agent(_) => "agent",
+            AppError::ApprovalNotFound => "approval_not_found",
+            AppError::InvalidShortcut(_) => "invalid_shortcut",
+            AppError::ShortcutConflict(_) => "shortcut_conflict",
+            AppError::ShortcutUnavailable(_) => "shortcut_unavailable",
+            AppError::ShortcutNotBound => "shortcut_not_bound",
+            AppError::InvalidSettings(_) => "invalid_settings",
+            AppError::Autostart(_) => "autostart",
+            AppError::Updater(_) => "updater",
+            AppError::InvalidMagicLink => "invalid_magic_link",
+            AppError::InvalidDeepLink(_) => "invalid_deep_link",
+            AppError::LogExport(_) => "log_export",
+            AppError::Internal(_) => "internal",
+        }
+    }
+
+    /// Технические подробности (текст ошибки ОС, тело ответа бекенда), не локализуются
+    pub fn details(&self) -> Option<String> {
+        match self {
+            AppError::Network(d)
+            | AppError::InvalidResponse(d)
+            | AppError::Credentials(d)
+            | AppError::HelperFailed(d)
+            | AppError::PythonUnavailable(d)
+            | AppError::MonitorNotFound(d)
+            | AppError::InvalidShortcut(d)
+            | AppError::ShortcutConflict(d)
+            | AppError::ShortcutUnavailable(d)
+            | AppError::InvalidSettings(d)
+            | AppError::Autostart(d)
+            | AppError::Updater(d)
+            | AppError::InvalidDeepLink(d)
+            | AppError::LogExport(d)
+            | AppError::Agent(d)
+            | AppError::Internal(d) => Some(d.clone()),
+            AppError::Backend { status, body } => Some(format!("HTTP {}: {}", status, body)),
+            _ => None,
+        }
+    }
+
+    pub fn message(&self) -> &'static str {
+        let ru = lang() == Lang::Ru;
+        match self {
+            AppError::Network(_) => if ru { "Нет соединения с сервером" } else { "Cannot reach the server" },
+            AppError::InvalidCredentials => if ru { "Неверный email или пароль" } else { "Invalid email or password" },
+            AppError::RateLimited => if ru { "Слишком много попыток входа, попробуйте позже" } else { "Too many login attempts, try again later" },
+            AppError::AccountLocked => if ru { "Аккаунт временно заблокирован" } else { "Account temporarily locked" },
+            AppError::EmailInUse => if ru { "Этот email уже зарегистрирован" } else { "Email already in use" },
+            AppError::NotAuthenticated => if ru { "Необходимо войти в аккаунт" } else { "Please sign in first" },
+            AppError::SessionExpired => if ru { "Сессия истекла, войдите снова" } else { "Session expired, please sign in again" },
+            AppError::DeviceNotEnrolled => if ru { "Устройство не зарегистрировано" } else { "Device is not enrolled" },
+            AppError::Backend { .. } => if ru { "Сервер вернул ошибку" } else { "The server returned an error" },
+            AppError::InvalidResponse(_) => if ru { "Не удалось разобрать ответ сервера" } else { "Failed to parse server response" },
+            AppError::Credentials(_) => if ru { "Ошибка хранилища учётных данных" } else { "Credential storage error" },
+            AppError::HelperNotRunning => if ru { "Рамка не запущена" } else { "Outline is not running" },
+            AppError::HelperFailed(_) => if ru { "OutlineHelper не отвечает" } else { "OutlineHelper is not responding" },
+            AppError::PythonUnavailable(_) => if ru { "Python-окружение недоступно" } else { "Python environment is unavailable" },
+            AppError::NoMonitor => if ru { "Не удалось получить информацию о мониторе" } else { "Failed to get monitor information" },
+            AppError::MonitorNotFound(_) => if ru { "Монитор не подключён" } else { "Monitor is not connected" },
+            AppError::Agent(_) => if ru { "Ошибка агента устройства" } else { "Device agent error" },
+            AppError::ApprovalNotFound => if ru { "Запрос подтверждения уже закрыт" } else { "Approval request is no longer pending" },
+            AppError::InvalidShortcut(_) => if ru { "Некорректное сочетание клавиш" } else { "Invalid shortcut" },
+            AppError::ShortcutConflict(_) => if ru { "Сочетание уже назначено другому действию" } else { "Shortcut is already assigned to another action" },
+            AppError::ShortcutUnavailable(_) => if ru { "Система не дала зарегистрировать сочетание" } else { "The system refused to register the shortcut" },
+            AppError::ShortcutNotBound => if ru { "Для действия не назначено сочетание" } else { "No shortcut is assigned to this action" },
+            AppError::InvalidSettings(_) => if ru { "Некорректные настройки" } else { "Invalid settings" },
+            AppError::Autostart(_) => if ru { "Не удалось изменить автозапуск" } else { "Failed to change launch at login" },
+            AppError::Updater(_) => if ru { "Ошибка обновления" } else { "Update failed" },
+            AppError::InvalidMagicLink => if ru { "Ссылка для входа недействительна или устарела" } else { "Sign-in link is invalid or expired" },
+            AppError::InvalidDeepLink(_) => if ru { "Некорректная ссылка skygen://" } else { "Invalid skygen:// link" },
+            AppError::LogExport(_) => if ru { "Не удалось выгрузить журналы" } else { "Failed to export logs" },
+            AppError::Internal(_) => if ru { "Внутренняя ошибка" } else { "Internal error" },
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.details() {
+            Some(details) => write!(f, "{}: {}", self.message(), details),
+            None => write!(f, "{}", self.message()),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("AppError", 3)?;
+        s.serialize_field("code", self.code())?;
+        s.serialize_field("message", self.message())?;
+        s.serialize_field("details", &self.details())?;
+        s.end()
+    }
+}
+
+impl From<ClientError> for AppError {
+    fn from(e: ClientError) -> Self {
+        match e {
+            ClientError::Network(e) => AppError::Network(e),
+            ClientError::InvalidCredentials => AppError::InvalidCredentials,
+            ClientError::RateLimited => AppError::RateLimited,
+            ClientError::AccountLocked => AppError::AccountLocked,
+            ClientError::EmailInUse => AppError::EmailInUse,
+            ClientError::InvalidMagicLink => AppError::InvalidMagicLink,
+            ClientError::NotAuthenticated => AppError::NotAuthenticated,
+            ClientError::SessionExpired => AppError::SessionExpired,
+            ClientError::Backend { status, body } => AppError::Backend { status, body },
+            ClientError::InvalidResponse(e) => AppError::InvalidResponse(e),
+        }
+    }
+}
+
+impl From<CredentialError> for AppError {
+    fn from(e: CredentialError) -> Self {
+        AppError::Credentials(e.to_string())
+    }
+}
+
+impl From<tauri::Error> for AppError {
+    fn from(e: tauri::Error) -> Self {
+        AppError::Internal(e.to_string())
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(e: std::io::Error) -> Self {
+        AppError::Internal(e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    // code и форма {code, message, details} — контракт с SkygenError во фронтенде
+    #[test]
+    fn serializes_stable_code_message_and_details() {
+        let cases = [
+            (AppError::InvalidCredentials, "invalid_credentials", None),
+            (AppError::SessionExpired, "session_expired", None),
+            (AppError::DeviceNotEnrolled, "device_not_enrolled", None),
+            (AppError::Network("timeout".into()), "network", Some("timeout")),
+            (AppError::InvalidSettings("outline.width".into()), "invalid_settings", Some("outline.width")),
+            (AppError::ShortcutConflict("cancel_task".into()), "shortcut_conflict", Some("cancel_task")),
+        ];
+        for (error, code, details) in cases {
+            assert_eq!(serde_json::to_value(&error).unwrap(), json!({ "code": code, "message": error.message(), "details": details }));
+        }
+    }
+
+    #[test]
+    fn backend_error_keeps_status_in_details() {
+        let error = AppError::Backend { status: 502, body: "bad gateway".into() };
+        let value = serde_json::to_value(&error).unwrap();
+        assert_eq!(value["code"], "backend");
+        assert_eq!(value["details"], "HTTP 502: bad gateway");
+    }
+
+    #[test]
+    fn client_errors_keep_their_codes() {
+        assert_eq!(AppError::from(ClientError::RateLimited).code(), "rate_limited");
+        assert_eq!(AppError::from(ClientError::AccountLocked).code(), "account_locked");
+        assert_eq!(AppError::from(ClientError::InvalidMagicLink).code(), "invalid_magic_link");
+    }
+}
@@ -0,0 +1,158 @@
+// Один экземпляр приложения: повторный запуск передаёт свои аргументы работающему экземпляру и выходит
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::deep_link;
+use crate::overlay::OverlayState;
+
+pub const SECOND_INSTANCE_EVENT: &str = "app://second-instance";
+pub const SHOW_OVERLAY_ARG: &str = "--show-overlay";
+
+/// Аргументы повторного запуска (argv[0] уже отброшен); UI получает их событием целиком
+pub fn handle_args(app: &AppHandle, args: Vec<String>) {
+    log::info!("second instance started with {:?}", args);
+    let _ = app.emit(SECOND_INSTANCE_EVENT, &args);
+
+    // На Linux и Windows ссылки skygen:// приходят именно так: система запускает приложение ещё раз
+    let links: Vec<&String> = args.iter().filter(|arg| deep_link::is_link(arg)).collect();
+    if !links.is_empty() {
+        for link in links {
+            deep_link::handle(app, link);
+        }
+        return;
+    }
+
+    let app = app.clone();
+    if args.iter().any(|arg| arg == SHOW_OVERLAY_ARG) {
+        tauri::async_runtime::spawn_blocking(move || {
+            if let Err(e) = app.state::<OverlayState>().lock().unwrap().show(&app) {
+                log::warn!("failed to show overlay: {}", e);
+            }
+        });
+    } else if !args.iter().any(|arg| arg == crate::autostart::BACKGROUND_ARG) {
+        // Повторный клик по иконке приложения: поднимаем окно, а не второй процесс
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = crate::open_main_window(&app) {
+                log::warn!("failed to open main window: {}", e);
+            }
+        });
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub use lock::{acquire, Instance};
+
+/// Linux: flock на файле в каталоге пользователя и unix-сокет рядом с ним.
+/// Плагин single-instance здесь ходит через D-Bus, которого на headless-машинах агентов нет.
+#[cfg(target_os = "linux")]
+mod lock {
+    use std::fs::{self, File, OpenOptions};
+    use std::io::{self, Read, Write};
+    use std::os::unix::io::AsRawFd;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::{Path, PathBuf};
+    use std::time::Duration;
+    use tauri::AppHandle;
+
+    const CONNECT_ATTEMPTS: u32 = 20;
+
+    pub enum Instance {
+        Primary(InstanceLock),
+        /// Работает другой экземпляр, этому процессу остаётся выйти; внутри — удалось ли передать ему аргументы
+        Secondary(io::Result<()>),
+    }
+
+    pub struct InstanceLock {
+        // Блокировка держится, пока открыт файл, то есть до конца процесса
+        _file: File,
+        listener: UnixListener,
+    }
+
+    impl InstanceLock {
+        /// Принимает аргументы следующих запусков до конца работы приложения
+        pub fn listen(self, app: AppHandle) {
+            std::thread::spawn(move || {
+                for stream in self.listener.incoming() {
+                    match stream.and_then(read_args) {
+                        Ok(args) => super::handle_args(&app, args.into_iter().skip(1).collect()),
+                        Err(e) => log::warn!("ignoring instance message: {}", e),
+                    }
+                }
+            });
+        }
+    }
+
+    pub fn acquire() -> io::Result<Instance> {
+        acquire_in(&runtime_dir())
+    }
+
+    fn acquire_in(dir: &Path) -> io::Result<Instance> {
+        fs::create_dir_all(dir)?;
+        let socket = dir.join("instance.sock");
+        let file = OpenOptions::new().create(true).truncate(false).write(true).open(dir.join("instance.lock"))?;
+
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } != 0 {
+            let error = io::Error::last_os_error();
+            if error.kind() != io::ErrorKind::WouldBlock {
+                return Err(error);
+            }
+            // Даже если передать аргументы не вышло, второй агент с тем же токеном запускать нельзя
+            return Ok(Instance::Secondary(forward(&socket)));
+        }
+
+        // Сокет мог остаться от упавшего экземпляра: блокировка у нас, значит он ничей
+        let _ = fs::remove_file(&socket);
+        let listener = UnixListener::bind(&socket)?;
+        Ok(Instance::Primary(InstanceLock { _file: file, listener }))
+    }
+
+    // Первый экземпляр мог взять блокировку, но ещё не открыть сокет
+    fn forward(socket: &Path) -> io::Result<()> {
+        let args: Vec<String> = std::env::args().collect();
+        let mut attempt = 0;
+        let mut stream = loop {
+            match UnixStream::connect(socket) {
+                Ok(stream) => break stream,
+                Err(_) if attempt + 1 < CONNECT_ATTEMPTS => {
+                    attempt += 1;
+                    std::thread::sleep(Duration::from_millis(100));
+                }
+                Err(e) => return Err(e),
+            }
+        };
+        stream.write_all(&serde_json::to_vec(&args)?)?;
+        Ok(())
+    }
+
+    /// Полный argv следующего запуска, включая argv[0]
+    fn read_args(mut stream: UnixStream) -> io::Result<Vec<String>> {
+        let mut buf = String::new();
+        stream.read_to_string(&mut buf)?;
+        Ok(serde_json::from_str(&buf)?)
+    }
+
+    /// Каталог на пользователя: XDG_RUNTIME_DIR, иначе временный каталог с uid в имени
+    fn runtime_dir() -> PathBuf {
+        match std::env::var_os("XDG_RUNTIME_DIR") {
+            Some(dir) => PathBuf::from(dir).join("com.skygen.desktop"),
+            None => std::env::temp_dir().join(format!("com.skygen.desktop-{}", unsafe { libc::getuid() })),
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn second_instance_forwards_its_args_to_the_first() {
+            let dir = std::env::temp_dir().join(format!("skygen-instance-{}", uuid::Uuid::new_v4()));
+            let Instance::Primary(lock) = acquire_in(&dir).unwrap() else { panic!("first launch must take the lock") };
+
+            assert!(matches!(acquire_in(&dir).unwrap(), Instance::Secondary(Ok(()))));
+            let (stream, _) = lock.listener.accept().unwrap();
+            assert_eq!(read_args(stream).unwrap(), std::env::args().collect::<Vec<_>>());
+
+            drop(lock);
+            fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+}
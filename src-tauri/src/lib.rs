@@ -1,22 +1,46 @@
-use tauri::{Manager, WebviewWindowBuilder, Listener};
-use std::sync::Mutex;
-use std::process::{Child, ChildStdin, Command};
+use tauri::{Emitter, Manager, WebviewWindowBuilder};
+use std::sync::Arc;
+use std::process::Command;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
+use agent::dispatcher::{Activity, Dispatcher};
+use agent::audit::{AuditEvent, AuditLog, AuditVerification};
+use agent::executor::{ActionKind, ExecutorRegistry};
+use agent::policy::{Approval, ApprovalRequest, Policy, Rule, RuleDecision, APPROVAL_EVENT};
+use agent::{Agent, AgentState, ConnectionState};
+use deep_link::PendingReferral;
+use display::OverlayTarget;
+use error::AppError;
+use outline::{ModeDriver, OutlineMode, OutlineState};
+use overlay::OverlayState;
+use settings::{LogLevel, ProfileId, Settings, SettingsStore};
+use shortcuts::{ShortcutAction, ShortcutInfo, Shortcuts};
+use skygen_client::{SkygenClient, SkygenState};
+use tauri_plugin_deep_link::DeepLinkExt;
+use updater::{UpdateStatus, Updater};
 #[cfg(target_os = "macos")]
 use cocoa::appkit::NSWindowCollectionBehavior;
 #[cfg(target_os = "macos")]
-use cocoa::base::{id, NO};
-#[cfg(target_os = "macos")]
-use objc::runtime::YES;
+use cocoa::base::id;
 #[cfg(target_os = "macos")]
 use objc::{msg_send, sel, sel_impl};
 
-struct OverlayState {
-    visible: bool,
-    outline_process: Option<Child>,
-    outline_stdin: Option<ChildStdin>,
-}
+mod agent;
+mod autostart;
+mod capture;
+mod credentials;
+mod deep_link;
+mod display;
+mod error;
+mod instance;
+mod logging;
+mod outline;
+mod overlay;
+mod settings;
+mod shortcuts;
+mod skygen_client;
+mod tray;
+mod updater;
 
 #[derive(Debug, Serialize, Deserialize)]
 struct AuthResponse {
@@ -27,6 +51,13 @@ struct AuthResponse {
     error: Option<String>,
 }
 
+impl AuthResponse {
+    // Ошибки уходят в UI как AppError, поэтому здесь только успешный ответ
+    fn ok<T: Serialize>(data: T) -> Self {
+        Self { success: true, data: serde_json::to_value(data).ok(), error: None }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct StatusResponse {
     authenticated: bool,
@@ -36,302 +67,159 @@ struct StatusResponse {
     device_id: Option<String>,
     platform: String,
     desktop_env_available: bool,
+    action_backends: Vec<String>,
 }
 
-impl Default for OverlayState {
-    fn default() -> Self {
-        Self { visible: false, outline_process: None, outline_stdin: None }
-    }
+#[tauri::command]
+async fn show_overlay(app: tauri::AppHandle, state: tauri::State<'_, OverlayState>) -> Result<(), AppError> {
+    state.lock().unwrap().show(&app)
 }
 
 #[tauri::command]
-fn show_overlay(app: tauri::AppHandle, state: tauri::State<'_, Mutex<OverlayState>>) -> Result<(), String> {
-    let mut overlay_state = state.lock().unwrap();
-    
-    if overlay_state.visible {
-        println!("Оверлей уже показан");
-        return Ok(());
-    }
-
-    println!("Начинаем показ оверлея...");
-    
-    // Добавляем задержку перед появлением оверлея  
-    std::thread::sleep(std::time::Duration::from_millis(500));
-
-    // Получаем размеры экрана
-    let monitors = app.primary_monitor().map_err(|e| e.to_string())?;
-    let monitor = monitors.ok_or("Не удалось получить информацию о мониторе")?;
-    let scale_factor = monitor.scale_factor();
-    let screen_size_physical = monitor.size();
-    let screen_size = screen_size_physical.to_logical::<f64>(scale_factor);
-    
-    // Размеры и позиция оверлея (панель по центру экрана)
-    let overlay_width = 720;
-    let overlay_height = 120;
-    // Центр экрана
-    let x = (screen_size.width as i32 / 2) - (overlay_width as i32 / 2);
-    let y = (screen_size.height as i32 / 2) - (overlay_height as i32 / 2);
-    
-    println!("Размер экрана: {}x{}", screen_size.width, screen_size.height);
-    println!("Размер оверлея: {}x{}", overlay_width, overlay_height);
-    println!("Центр экрана: x={}, y={}", screen_size.width / 2.0, screen_size.height / 2.0);
-    println!("Позиция оверлея: x={}, y={}", x, y);
-    println!("Должно быть по центру: x={}, y={}", 
-             ((screen_size.width / 2.0) - (overlay_width as f64 / 2.0)) as i32,
-             ((screen_size.height / 2.0) - (overlay_height as f64 / 2.0)) as i32);
-
-    // Основная панель-оверлей
-    let overlay_window = WebviewWindowBuilder::new(
-        &app,
-        "overlay",
-        tauri::WebviewUrl::App("overlay".into())
-    )
-    .title("Overlay")
-    .inner_size(overlay_width as f64, overlay_height as f64)
-    .position(x as f64, y as f64)
-    .resizable(false)
-    .decorations(false)
-    .always_on_top(true)
-    .skip_taskbar(true)
-    .transparent(true)
-    .visible(true)
-    .build()
-    .map_err(|e| e.to_string())?;
-
-    // Принудительно показываем окно
-    overlay_window.show().map_err(|e| e.to_string())?;
-    // Центрируем после показа (на случай неверной логической/физической конверсии)
-    let _ = overlay_window.set_position(tauri::LogicalPosition::new(
-        (screen_size.width - overlay_width as f64) / 2.0,
-        (screen_size.height - overlay_height as f64) / 2.0,
-    ));
-    
-    println!("Окно оверлея создано и показано в позиции x={}, y={}", x, y);
+async fn hide_overlay(state: tauri::State<'_, OverlayState>) -> Result<(), AppError> {
+    state.lock().unwrap().hide()
+}
 
-    // macOS: повысим уровень окна, включим показ во всех пространствах и поверх fullscreen (на главном потоке)
-    #[cfg(target_os = "macos")]
-    {
-        let overlay_clone = overlay_window.clone();
-        let app_handle = app.clone();
-        let _ = app_handle.run_on_main_thread(move || unsafe {
-            if let Ok(ns_win_ptr) = overlay_clone.ns_window() {
-                let ns_win: id = ns_win_ptr as id;
-                let level: i32 = 101; // уровень панели
-                let _: () = msg_send![ns_win, setLevel: level];
-                let behavior = NSWindowCollectionBehavior::NSWindowCollectionBehaviorCanJoinAllSpaces
-                    | NSWindowCollectionBehavior::NSWindowCollectionBehaviorFullScreenAuxiliary
-                    | NSWindowCollectionBehavior::NSWindowCollectionBehaviorTransient;
-                let _: () = msg_send![ns_win, setCollectionBehavior: behavior];
-                let _: () = msg_send![ns_win, setOpaque: NO];
-                let _: () = msg_send![ns_win, setHasShadow: YES];
-            }
-        });
-    }
-    
-    // Не перехватываем фокус у активного приложения (если есть главное окно — вернём фокус)
-    if let Some(main_window) = app.get_webview_window("main") {
-        let _ = main_window.set_focus();
-    }
+#[tauri::command]
+async fn show_dim(app: tauri::AppHandle, state: tauri::State<'_, OverlayState>) -> Result<(), AppError> {
+    state.lock().unwrap().show_dim(&app)
+}
 
-    overlay_state.visible = true;
-    println!("Состояние оверлея установлено как видимый");
-    
-    // Убеждаемся, что переменная используется и не удаляется
-    std::mem::forget(overlay_window);
-    
+#[tauri::command]
+async fn hide_dim(state: tauri::State<'_, OverlayState>) -> Result<(), AppError> {
+    state.lock().unwrap().hide_dim();
     Ok(())
 }
 
 #[tauri::command]
-fn hide_overlay(app: tauri::AppHandle, state: tauri::State<'_, Mutex<OverlayState>>) -> Result<(), String> {
-    let mut overlay_state = state.lock().unwrap();
-    
-    if !overlay_state.visible {
-        return Ok(());
-    }
+fn list_shortcuts(shortcuts: tauri::State<'_, Shortcuts>) -> Vec<ShortcutInfo> {
+    shortcuts.list()
+}
 
-    if let Some(overlay_window) = app.get_webview_window("overlay") {
-        overlay_window.close().map_err(|e| e.to_string())?;
-    }
-    if let Some(dim_window) = app.get_webview_window("overlay_dim") {
-        dim_window.close().map_err(|e| e.to_string())?;
-    }
+#[tauri::command]
+async fn register_shortcut(app: tauri::AppHandle, shortcuts: tauri::State<'_, Shortcuts>, action: ShortcutAction, accelerator: String) -> Result<Vec<ShortcutInfo>, AppError> {
+    shortcuts.bind(&app, action, &accelerator)?;
+    Ok(shortcuts.list())
+}
 
-    overlay_state.visible = false;
-    Ok(())
+#[tauri::command]
+async fn rebind_shortcut(app: tauri::AppHandle, shortcuts: tauri::State<'_, Shortcuts>, action: ShortcutAction, accelerator: String) -> Result<Vec<ShortcutInfo>, AppError> {
+    shortcuts.rebind(&app, action, &accelerator)?;
+    Ok(shortcuts.list())
 }
 
 #[tauri::command]
-fn show_dim(app: tauri::AppHandle) -> Result<(), String> {
-    // Create or show dim window
-    if app.get_webview_window("overlay_dim").is_none() {
-        let monitors = app.primary_monitor().map_err(|e| e.to_string())?;
-        let monitor = monitors.ok_or("Не удалось получить информацию о мониторе")?;
-        let scale = monitor.scale_factor();
-        let logical = monitor.size().to_logical::<f64>(scale);
-        let dim = WebviewWindowBuilder::new(
-            &app,
-            "overlay_dim",
-            tauri::WebviewUrl::App("overlay/background".into())
-        )
-        .title("Dim")
-        .inner_size(logical.width, logical.height)
-        .position(0.0, 0.0)
-        .resizable(false)
-        .decorations(false)
-        .always_on_top(true)
-        .skip_taskbar(true)
-        .transparent(true)
-        .visible(true)
-        .build()
-        .map_err(|e| e.to_string())?;
-
-        // macOS: сделать окно диммера кликабельным насквозь и видимым во всех пространствах
-        #[cfg(target_os = "macos")]
-        {
-            let app_handle = app.clone();
-            let dim_clone = dim.clone();
-            let _ = app_handle.run_on_main_thread(move || unsafe {
-                if let Ok(ns_win_ptr) = dim_clone.ns_window() {
-                    let ns_win: id = ns_win_ptr as id;
-                    let level: i32 = 100; // ниже панели
-                    let _: () = msg_send![ns_win, setLevel: level];
-                    let behavior = NSWindowCollectionBehavior::NSWindowCollectionBehaviorCanJoinAllSpaces
-                        | NSWindowCollectionBehavior::NSWindowCollectionBehaviorFullScreenAuxiliary
-                        | NSWindowCollectionBehavior::NSWindowCollectionBehaviorTransient;
-                    let _: () = msg_send![ns_win, setCollectionBehavior: behavior];
-                    let _: () = msg_send![ns_win, setOpaque: NO];
-                    let _: () = msg_send![ns_win, setIgnoresMouseEvents: YES];
-                }
-            });
-        }
-    } else if let Some(win) = app.get_webview_window("overlay_dim") {
-        let _ = win.show();
-        let _ = win.set_focus();
-    }
-    Ok(())
+async fn unregister_shortcut(app: tauri::AppHandle, shortcuts: tauri::State<'_, Shortcuts>, action: ShortcutAction) -> Result<Vec<ShortcutInfo>, AppError> {
+    shortcuts.unbind(&app, action)?;
+    Ok(shortcuts.list())
+}
+
+#[tauri::command]
+fn list_monitors(app: tauri::AppHandle) -> Result<Vec<display::MonitorInfo>, AppError> {
+    display::list(&app)
 }
 
 #[tauri::command]
-fn hide_dim(app: tauri::AppHandle) -> Result<(), String> {
-    if let Some(win) = app.get_webview_window("overlay_dim") {
-        let _ = win.close();
+fn get_overlay_target(settings: tauri::State<'_, SettingsStore>) -> OverlayTarget {
+    settings.get().overlay.target
+}
+
+#[tauri::command]
+async fn set_overlay_target(app: tauri::AppHandle, settings: tauri::State<'_, SettingsStore>, target: OverlayTarget) -> Result<(), AppError> {
+    if let OverlayTarget::Monitor { name } = &target {
+        if !display::list(&app)?.iter().any(|m| m.name.as_ref() == Some(name)) {
+            return Err(AppError::MonitorNotFound(name.clone()));
+        }
     }
+    let mut updated = settings.get();
+    updated.overlay.target = target;
+    save_settings(&app, &settings, updated).await?;
     Ok(())
 }
 
 #[tauri::command]
-fn start_outline(_app: tauri::AppHandle, state: tauri::State<'_, Mutex<OverlayState>>, color: String, width: Option<u32>, blur: Option<u32>) -> Result<(), String> {
-    #[cfg(not(target_os = "macos"))]
-    {
-        return Err("Outline доступен только на macOS".into());
-    }
+fn get_settings(settings: tauri::State<'_, SettingsStore>) -> Settings {
+    settings.get()
+}
 
-    #[cfg(target_os = "macos")]
-    {
-        use std::path::PathBuf;
-        use std::process::{Command, Stdio};
-
-        // Если уже запущен — сначала остановим
-        {
-            let mut s = state.lock().unwrap();
-            if let Some(mut child) = s.outline_process.take() {
-                let _ = child.kill();
-            }
-        }
+#[tauri::command]
+async fn update_settings(app: tauri::AppHandle, store: tauri::State<'_, SettingsStore>, settings: Settings) -> Result<Settings, AppError> {
+    save_settings(&app, &store, settings).await
+}
 
-        // 1) Явный путь через переменную окружения
-        if let Ok(explicit) = std::env::var("OUTLINE_HELPER_PATH") {
-            let mut cmd = Command::new(explicit);
-            cmd.arg("--color").arg(&color);
-            if let Some(w) = width { cmd.arg("--width").arg(w.to_string()); }
-            if let Some(b) = blur { cmd.arg("--blur").arg(b.to_string()); }
-            let mut child = cmd
-                .stdin(Stdio::piped())
-                .stdout(Stdio::null())
-                .stderr(Stdio::null())
-                .spawn()
-                .map_err(|e| e.to_string())?;
-            let mut s = state.lock().unwrap();
-            s.outline_stdin = child.stdin.take();
-            s.outline_process = Some(child);
-            return Ok(())
-        }
+#[tauri::command]
+async fn set_backend_profile(app: tauri::AppHandle, profile: ProfileId) -> Result<Settings, AppError> {
+    select_profile(&app, profile).await
+}
 
-        // 2) Относительно рабочей директории (обычно src-tauri)
-        let mut candidates: Vec<PathBuf> = Vec::new();
-        if let Ok(cwd) = std::env::current_dir() {
-            let mut p = cwd.clone();
-            p.push("macos/OutlineHelper/OutlineHelper");
-            candidates.push(p);
-        }
-        // 3) Относительно корня проекта (поднимаемся на два уровня из exe)
-        if let Ok(exe) = std::env::current_exe() {
-            if let Some(root) = exe.parent().and_then(|p| p.parent()).and_then(|p| p.parent()) {
-                let mut p = root.to_path_buf();
-                p.push("src-tauri/macos/OutlineHelper/OutlineHelper");
-                candidates.push(p);
-            }
-        }
+/// Переключение профиля из UI и из трея
+async fn select_profile(app: &tauri::AppHandle, profile: ProfileId) -> Result<Settings, AppError> {
+    let store = app.state::<SettingsStore>();
+    let mut settings = store.get();
+    settings.backend.profile = profile;
+    save_settings(app, &store, settings).await
+}
 
-        let helper_path = candidates.into_iter().find(|p| p.exists())
-            .ok_or_else(|| "Не найден OutlineHelper. Соберите через: bash skygen-ui/src-tauri/macos/build_outline_helper.sh или задайте OUTLINE_HELPER_PATH".to_string())?;
-
-        let mut cmd = Command::new(helper_path);
-        cmd.arg("--color").arg(&color);
-        if let Some(w) = width { cmd.arg("--width").arg(w.to_string()); }
-        if let Some(b) = blur { cmd.arg("--blur").arg(b.to_string()); }
-        let mut child = cmd
-            .stdin(Stdio::piped())
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .spawn()
-            .map_err(|e| e.to_string())?;
-
-        let mut s = state.lock().unwrap();
-        s.outline_stdin = child.stdin.take();
-        s.outline_process = Some(child);
-        Ok(())
+async fn save_settings(app: &tauri::AppHandle, store: &SettingsStore, settings: Settings) -> Result<Settings, AppError> {
+    let previous = store.get();
+    // Запись автозапуска меняется до сохранения: если ОС отказала, настройки остаются прежними
+    if settings.autostart != previous.autostart {
+        settings.validate()?;
+        autostart::sync(app, settings.autostart)?;
+    }
+    let updated = store.update(settings)?;
+    // Смена окружения: агент старого профиля отключается, сессия и устройство берутся из хранилища нового
+    if updated.backend.profile != previous.backend.profile || updated.backend.active() != previous.backend.active() {
+        app.state::<Agent>().stop(app);
+        app.state::<Arc<Policy>>().reset_session();
+        let profile = updated.backend.active();
+        let credentials = credentials::open(&app.path().app_data_dir()?, updated.backend.profile);
+        log::info!("backend profile: {} ({})", updated.backend.profile.name(), profile.url);
+        app.state::<SkygenState>().lock().await.switch_profile(&profile.url, profile.wss_url, credentials);
     }
+    // Обновление, найденное в другом канале или фиде, больше не актуально
+    if updated.updates.endpoint() != previous.updates.endpoint() {
+        app.state::<Updater>().discard(app);
+    }
+    if updated.shortcuts != previous.shortcuts {
+        app.state::<Shortcuts>().apply(app, &updated.shortcuts);
+    }
+    apply_settings(app, &updated)?;
+    let _ = app.emit(settings::CHANGED_EVENT, &updated);
+    Ok(updated)
+}
+
+/// Применяет то, что меняется без перезапуска; адрес бекенда меняется отдельно, под блокировкой клиента
+fn apply_settings(app: &tauri::AppHandle, settings: &Settings) -> Result<(), AppError> {
+    log::set_max_level(settings.log_level.filter());
+    let overlay = &settings.overlay;
+    app.state::<OverlayState>().lock().unwrap().configure(overlay.target.clone(), overlay.width, overlay.height);
+    let outline = &settings.outline;
+    app.state::<OutlineState>().lock().unwrap().configure(outline.color.clone(), outline.width, outline.blur)
 }
 
 #[tauri::command]
-fn stop_outline(_app: tauri::AppHandle, state: tauri::State<'_, Mutex<OverlayState>>) -> Result<(), String> {
-    let mut s = state.lock().unwrap();
-    s.outline_stdin.take();
-    if let Some(mut child) = s.outline_process.take() {
-        let _ = child.kill();
-        #[cfg(target_os = "macos")]
-        {
-            // На случай, если процесс ещё жив — добиваем pkill по имени
-            let _ = std::process::Command::new("/usr/bin/pkill")
-                .arg("-f")
-                .arg("OutlineHelper")
-                .status();
-        }
-    }
+async fn start_outline(app: tauri::AppHandle, state: tauri::State<'_, OutlineState>, color: String, width: Option<u32>, blur: Option<u32>) -> Result<(), AppError> {
+    state.lock().unwrap().start(&app, color, width, blur)
+}
+
+#[tauri::command]
+async fn stop_outline(app: tauri::AppHandle, state: tauri::State<'_, OutlineState>) -> Result<(), AppError> {
+    state.lock().unwrap().stop(&app);
     Ok(())
 }
 
 #[tauri::command]
-fn update_outline(_app: tauri::AppHandle, state: tauri::State<'_, Mutex<OverlayState>>, color: Option<String>, width: Option<u32>, blur: Option<u32>) -> Result<(), String> {
-    let mut s = state.lock().unwrap();
-    if let Some(stdin) = s.outline_stdin.as_mut() {
-        let mut payload = serde_json::Map::new();
-        if let Some(c) = color { payload.insert("color".into(), serde_json::Value::String(c)); }
-        if let Some(w) = width { payload.insert("width".into(), serde_json::Value::Number(w.into())); }
-        if let Some(b) = blur { payload.insert("blur".into(), serde_json::Value::Number(b.into())); }
-        let msg = serde_json::Value::Object(payload).to_string() + "\n";
-        use std::io::Write;
-        stdin.write_all(msg.as_bytes()).map_err(|e| e.to_string())?;
-        let _ = stdin.flush();
-        Ok(())
-    } else {
-        Err("OutlineHelper не запущен".into())
-    }
+async fn update_outline(state: tauri::State<'_, OutlineState>, color: Option<String>, width: Option<u32>, blur: Option<u32>) -> Result<(), AppError> {
+    state.lock().unwrap().update(color, width, blur)
+}
+
+#[tauri::command]
+async fn set_outline_mode(app: tauri::AppHandle, state: tauri::State<'_, OutlineState>, mode: OutlineMode) -> Result<(), AppError> {
+    state.lock().unwrap().set_mode(&app, mode)
 }
 
-fn get_python_path() -> Result<PathBuf, String> {
+fn get_python_path() -> Result<PathBuf, AppError> {
     // Находим Python скрипт относительно текущего exe или рабочей директории
     if let Ok(exe) = std::env::current_exe() {
         if let Some(exe_dir) = exe.parent() {
@@ -360,175 +248,442 @@ fn get_python_path() -> Result<PathBuf, String> {
         }
     }
     
-    Err("Python script not found".to_string())
+    Err(AppError::PythonUnavailable("Python script not found".into()))
 }
 
-async fn run_python_command(args: &[&str]) -> Result<String, String> {
-    let python_path = get_python_path()?;
-    
-    let output = Command::new("python3")
-        .arg(&python_path)
-        .args(args)
-        .output()
-        .map_err(|e| format!("Failed to execute Python: {}", e))?;
-    
-    if output.status.success() {
-        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+#[tauri::command]
+async fn skygen_login(app: tauri::AppHandle, state: tauri::State<'_, SkygenState>, email: String, password: String) -> Result<AuthResponse, AppError> {
+    let data = state.lock().await.login(&email, &password).await?;
+    tray::refresh(&app);
+    Ok(AuthResponse::ok(data))
+}
+
+#[tauri::command]
+async fn skygen_signup(state: tauri::State<'_, SkygenState>, email: String, password: String) -> Result<AuthResponse, AppError> {
+    let client = state.lock().await;
+    Ok(AuthResponse::ok(client.signup(&email, &password).await?))
+}
+
+#[tauri::command]
+async fn skygen_enroll_device(app: tauri::AppHandle, state: tauri::State<'_, SkygenState>, device_name: Option<String>) -> Result<AuthResponse, AppError> {
+    let device = state.lock().await.enroll_device(device_name).await?;
+    tray::refresh(&app);
+    Ok(AuthResponse::ok(device))
+}
+
+#[tauri::command]
+async fn skygen_logout(app: tauri::AppHandle, state: tauri::State<'_, SkygenState>, agent: tauri::State<'_, Agent>) -> Result<(), AppError> {
+    agent.stop(&app);
+    app.state::<Arc<Policy>>().reset_session();
+    state.lock().await.logout();
+    tray::refresh(&app);
+    Ok(())
+}
+
+#[tauri::command]
+async fn skygen_connect(app: tauri::AppHandle, state: tauri::State<'_, SkygenState>, agent: tauri::State<'_, Agent>) -> Result<AuthResponse, AppError> {
+    start_agent(&app, &state, &agent).await?;
+
+    // Не блокируемся на всё время соединения: ждём только результат регистрации
+    match agent.wait_started(std::time::Duration::from_secs(15)).await {
+        AgentState::Failed { error } => Err(AppError::Agent(error)),
+        agent_state => Ok(AuthResponse::ok(serde_json::json!({
+            "connected": agent_state == AgentState::Running,
+            "state": agent_state,
+        }))),
+    }
+}
+
+async fn start_agent(app: &tauri::AppHandle, state: &SkygenState, agent: &Agent) -> Result<(), AppError> {
+    if state.lock().await.device().is_none() {
+        return Err(AppError::DeviceNotEnrolled);
+    }
+
+    // Без бекендов соединение всё равно поднимается, но действия будут падать
+    let executors = app.state::<Arc<ExecutorRegistry>>().inner().clone();
+    let policy = app.state::<Arc<Policy>>().inner().clone();
+    policy.reset_session();
+    let audit = app.state::<Arc<AuditLog>>().inner().clone();
+    let outline = app.state::<Arc<ModeDriver>>().inner().clone();
+    let dispatcher = Dispatcher::new(executors, policy, audit).on_activity(Arc::new(move |activity| {
+        outline.request(match activity {
+            Activity::Working => OutlineMode::Working,
+            Activity::AwaitingApproval => OutlineMode::AwaitingApproval,
+            Activity::Idle => OutlineMode::Off,
+            Activity::Failed => OutlineMode::Error,
+            Activity::Cancelled => OutlineMode::Alert,
+        })
+    }));
+    agent.start(app, Arc::new(dispatcher));
+    Ok(())
+}
+
+#[tauri::command]
+async fn agent_start(app: tauri::AppHandle, state: tauri::State<'_, SkygenState>, agent: tauri::State<'_, Agent>) -> Result<AgentState, AppError> {
+    start_agent(&app, &state, &agent).await?;
+    Ok(agent.state())
+}
+
+#[tauri::command]
+fn agent_stop(app: tauri::AppHandle, agent: tauri::State<'_, Agent>) -> AgentState {
+    agent.stop(&app);
+    agent.state()
+}
+
+/// Аварийная остановка из любого места (хоткей, трей, UI): задача отменяется, рамка переходит в тревогу
+fn emergency_stop(app: &tauri::AppHandle) {
+    app.state::<Agent>().emergency_stop(app);
+    app.state::<Arc<ModeDriver>>().request(OutlineMode::Alert);
+}
+
+/// Главное окно создаётся из tauri.conf.json вручную (create: false), чтобы в фоновом режиме его не было вовсе
+fn open_main_window(app: &tauri::AppHandle) -> Result<(), AppError> {
+    if let Some(window) = app.get_webview_window("main") {
+        window.show()?;
+        window.set_focus()?;
+        return Ok(());
+    }
+    let config = app
+        .config()
+        .app
+        .windows
+        .iter()
+        .find(|window| window.label == "main")
+        .ok_or_else(|| AppError::Internal("main window is not configured".into()))?;
+    #[cfg_attr(not(target_os = "macos"), allow(unused_variables))]
+    let main_window = WebviewWindowBuilder::from_config(app, config)?.build()?;
+
+    // Настройка главного окна для поддержки полноэкранного режима
+    #[cfg(target_os = "macos")]
+    {
+        let _ = app.run_on_main_thread(move || unsafe {
+            if let Ok(ns_win_ptr) = main_window.ns_window() {
+                let ns_win: id = ns_win_ptr as id;
+                let behavior = NSWindowCollectionBehavior::NSWindowCollectionBehaviorCanJoinAllSpaces
+                    | NSWindowCollectionBehavior::NSWindowCollectionBehaviorFullScreenPrimary;
+                let _: () = msg_send![ns_win, setCollectionBehavior: behavior];
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Выход из трея: агент и рамка останавливаются, загруженное обновление ставится до завершения процесса
+fn quit(app: &tauri::AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        shutdown(&app);
+        if let Err(e) = app.state::<Updater>().install() {
+            log::error!("failed to install update on quit: {}", e);
+        }
+        app.exit(0);
+    });
+}
+
+fn shutdown(app: &tauri::AppHandle) {
+    app.state::<Agent>().stop(app);
+    app.state::<OutlineState>().lock().unwrap().stop(app);
+}
+
+/// Уровень журнала меняется сразу и сохраняется в настройках
+#[tauri::command]
+async fn set_log_level(app: tauri::AppHandle, store: tauri::State<'_, SettingsStore>, level: LogLevel) -> Result<Settings, AppError> {
+    let mut settings = store.get();
+    settings.log_level = level;
+    save_settings(&app, &store, settings).await
+}
+
+#[tauri::command]
+fn export_logs(app: tauri::AppHandle, destination: String) -> Result<usize, AppError> {
+    logging::export(&app, std::path::Path::new(&destination))
+}
+
+/// Реферальный код из последней ссылки skygen://ref/<code>; отдаётся один раз
+#[tauri::command]
+fn take_referral_code(referral: tauri::State<'_, PendingReferral>) -> Option<String> {
+    referral.take()
+}
+
+#[tauri::command]
+fn get_update_status(updater: tauri::State<'_, Updater>) -> UpdateStatus {
+    updater.status()
+}
+
+#[tauri::command]
+async fn check_for_updates(app: tauri::AppHandle, updater: tauri::State<'_, Updater>) -> Result<UpdateStatus, AppError> {
+    updater.check(&app).await
+}
+
+/// Ставит загруженное обновление и перезапускает приложение
+#[tauri::command]
+async fn apply_update(app: tauri::AppHandle, updater: tauri::State<'_, Updater>) -> Result<(), AppError> {
+    shutdown(&app);
+    if !updater.install()? {
+        return Err(AppError::Updater("no downloaded update".into()));
+    }
+    app.restart()
+}
+
+#[tauri::command]
+fn agent_emergency_stop(app: tauri::AppHandle) {
+    emergency_stop(&app);
+}
+
+#[tauri::command]
+fn agent_status(agent: tauri::State<'_, Agent>) -> AgentState {
+    agent.state()
+}
+
+#[tauri::command]
+fn agent_connection_state(agent: tauri::State<'_, Agent>) -> ConnectionState {
+    agent.connection_state()
+}
+
+#[tauri::command]
+fn pending_approvals(policy: tauri::State<'_, Arc<Policy>>) -> Vec<ApprovalRequest> {
+    policy.pending()
+}
+
+#[tauri::command]
+fn respond_approval(policy: tauri::State<'_, Arc<Policy>>, id: String, decision: Approval) -> Result<(), AppError> {
+    if policy.respond(&id, decision) {
+        Ok(())
     } else {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        Err(format!("Python command failed: {}", stderr))
+        Err(AppError::ApprovalNotFound)
     }
 }
 
 #[tauri::command]
-async fn skygen_login(email: String, password: String) -> Result<AuthResponse, String> {
-    let output = run_python_command(&["login", &email, &password]).await?;
-    
-    serde_json::from_str::<AuthResponse>(&output)
-        .map_err(|e| format!("Failed to parse response: {}", e))
+fn list_policy_rules(policy: tauri::State<'_, Arc<Policy>>) -> Vec<Rule> {
+    policy.rules()
 }
 
 #[tauri::command]
-async fn skygen_signup(email: String, password: String) -> Result<AuthResponse, String> {
-    let output = run_python_command(&["signup", &email, &password]).await?;
-    
-    serde_json::from_str::<AuthResponse>(&output)
-        .map_err(|e| format!("Failed to parse response: {}", e))
+fn set_policy_rule(policy: tauri::State<'_, Arc<Policy>>, action: ActionKind, decision: Option<RuleDecision>) -> Result<Vec<Rule>, AppError> {
+    policy.set_rule(action, decision)?;
+    Ok(policy.rules())
 }
 
 #[tauri::command]
-async fn skygen_enroll_device() -> Result<AuthResponse, String> {
-    let output = run_python_command(&["enroll"]).await?;
-    
-    serde_json::from_str::<AuthResponse>(&output)
-        .map_err(|e| format!("Failed to parse response: {}", e))
+fn list_audit_events(audit: tauri::State<'_, Arc<AuditLog>>, since: Option<String>, limit: Option<usize>) -> Result<Vec<AuditEvent>, AppError> {
+    Ok(audit.list(since.as_deref(), limit.unwrap_or(500))?)
 }
 
 #[tauri::command]
-async fn skygen_connect() -> Result<AuthResponse, String> {
-    let output = run_python_command(&["connect"]).await?;
-    
-    serde_json::from_str::<AuthResponse>(&output)
-        .map_err(|e| format!("Failed to parse response: {}", e))
+fn export_audit_log(audit: tauri::State<'_, Arc<AuditLog>>, destination: String) -> Result<usize, AppError> {
+    Ok(audit.export(std::path::Path::new(&destination))?)
 }
 
 #[tauri::command]
-async fn skygen_get_status() -> Result<StatusResponse, String> {
-    let output = run_python_command(&["test"]).await?;
-    
-    serde_json::from_str::<StatusResponse>(&output)
-        .map_err(|e| format!("Failed to parse response: {}", e))
+fn verify_audit_log(audit: tauri::State<'_, Arc<AuditLog>>) -> Result<AuditVerification, AppError> {
+    Ok(audit.verify()?)
 }
 
 #[tauri::command]
-fn install_dependencies() -> Result<String, String> {
+async fn skygen_get_status(
+    state: tauri::State<'_, SkygenState>,
+    agent: tauri::State<'_, Agent>,
+    executors: tauri::State<'_, Arc<ExecutorRegistry>>,
+) -> Result<StatusResponse, AppError> {
+    let client = state.lock().await;
+    Ok(StatusResponse {
+        authenticated: client.is_authenticated(),
+        device_enrolled: client.device().is_some(),
+        connected: agent.connection_state() == ConnectionState::Connected,
+        device_id: client.device().map(|d| d.device_id.clone()),
+        platform: skygen_client::platform_name().to_string(),
+        desktop_env_available: executors.controls_desktop(),
+        action_backends: executors.backend_names().into_iter().map(String::from).collect(),
+    })
+}
+
+#[tauri::command]
+fn install_dependencies() -> Result<String, AppError> {
     let python_dir = get_python_path()?.parent()
-        .ok_or("Failed to get python directory")?
+        .ok_or_else(|| AppError::PythonUnavailable("Failed to get python directory".into()))?
         .to_path_buf();
     
     let install_script = python_dir.join("install_desktop_env.py");
     if !install_script.exists() {
-        return Err("Installation script not found".to_string());
+        return Err(AppError::PythonUnavailable("Installation script not found".into()));
     }
     
     // Запускаем скрипт установки
     let output = Command::new("python3")
         .arg(&install_script)
         .output()
-        .map_err(|e| format!("Failed to run installation script: {}", e))?;
+        .map_err(|e| AppError::PythonUnavailable(format!("Failed to run installation script: {}", e)))?;
     
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !stderr.trim().is_empty() {
+        log::info!(target: "python", "{}", stderr.trim());
+    }
     if output.status.success() {
+        log::info!("desktop_env installation completed");
         let stdout = String::from_utf8_lossy(&output.stdout);
         Ok(format!("Installation completed successfully:\n{}", stdout))
     } else {
-        let stderr = String::from_utf8_lossy(&output.stderr);
+        log::error!("desktop_env installation failed: {}", output.status);
         let stdout = String::from_utf8_lossy(&output.stdout);
-        Err(format!("Installation failed:\nSTDOUT: {}\nSTDERR: {}", stdout, stderr))
+        Err(AppError::PythonUnavailable(format!("Installation failed:\nSTDOUT: {}\nSTDERR: {}", stdout, stderr)))
     }
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    tauri::Builder::default()
-        .manage(Mutex::new(OverlayState::default()))
+    // Linux: блокировка берётся до создания окон, трея и хоткеев, иначе второй процесс успел бы их поднять
+    #[cfg(target_os = "linux")]
+    let instance_lock = match instance::acquire() {
+        Ok(instance::Instance::Primary(lock)) => Ok(lock),
+        // Журнал во втором процессе не поднимается: неудачная передача аргументов видна только в stderr и коде выхода
+        Ok(instance::Instance::Secondary(Ok(()))) => return,
+        Ok(instance::Instance::Secondary(Err(e))) => {
+            eprintln!("failed to pass arguments to the running instance: {}", e);
+            std::process::exit(1)
+        }
+        // Ошибка попадёт в журнал, когда он будет открыт в setup
+        Err(e) => Err(e),
+    };
+
+    let builder = tauri::Builder::default();
+    // Остальные платформы: плагин должен быть зарегистрирован первым
+    #[cfg(not(target_os = "linux"))]
+    let builder = builder.plugin(tauri_plugin_single_instance::init(|app, argv, _cwd| {
+        instance::handle_args(app, argv.into_iter().skip(1).collect())
+    }));
+
+    builder
+        .manage(OverlayState::default())
+        .manage(OutlineState::default())
+        .manage(Agent::default())
+        .manage(Updater::default())
+        .manage(PendingReferral::default())
         .invoke_handler(tauri::generate_handler![
-            show_overlay, hide_overlay, show_dim, hide_dim, start_outline, stop_outline, update_outline,
-            skygen_login, skygen_signup, skygen_enroll_device, skygen_connect, skygen_get_status, install_dependencies
+            show_overlay, hide_overlay, show_dim, hide_dim, list_monitors, get_overlay_target, set_overlay_target,
+            start_outline, stop_outline, update_outline, set_outline_mode,
+            get_settings, update_settings, set_backend_profile, set_log_level, export_logs,
+            get_update_status, check_for_updates, apply_update, take_referral_code,
+            list_shortcuts, register_shortcut, rebind_shortcut, unregister_shortcut,
+            skygen_login, skygen_signup, skygen_enroll_device, skygen_logout, skygen_connect, skygen_get_status, install_dependencies,
+            agent_start, agent_stop, agent_emergency_stop, agent_status, agent_connection_state,
+            pending_approvals, respond_approval, list_policy_rules, set_policy_rule,
+            list_audit_events, export_audit_log, verify_audit_log
         ])
-        .setup(|app| {
-            // Настройка главного окна для поддержки полноэкранного режима
-            #[cfg(target_os = "macos")]
-            {
-                if let Some(main_window) = app.get_webview_window("main") {
-                    let main_clone = main_window.clone();
-                    let app_handle = app.handle().clone();
-                    let _ = app_handle.run_on_main_thread(move || unsafe {
-                        if let Ok(ns_win_ptr) = main_clone.ns_window() {
-                            let ns_win: id = ns_win_ptr as id;
-                            let behavior = NSWindowCollectionBehavior::NSWindowCollectionBehaviorCanJoinAllSpaces
-                                | NSWindowCollectionBehavior::NSWindowCollectionBehaviorFullScreenPrimary;
-                            let _: () = msg_send![ns_win, setCollectionBehavior: behavior];
-                        }
-                    });
+        // Закрытие главного окна только прячет его: агент продолжает работать в трее
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                if window.label() == "main" {
+                    api.prevent_close();
+                    let _ = window.hide();
                 }
             }
+        })
+        .setup(move |app| {
+            logging::init(app.handle())?;
+            log::info!("skygen {} starting on {} {}", app.package_info().version, std::env::consts::OS, std::env::consts::ARCH);
+
+            #[cfg(target_os = "linux")]
+            match instance_lock {
+                Ok(lock) => lock.listen(app.handle().clone()),
+                Err(e) => log::warn!("single-instance lock unavailable, continuing without it: {}", e),
+            }
+
+            let settings = SettingsStore::load(app.path().app_config_dir()?.join("settings.json"));
+            let current = settings.get();
+            log::set_max_level(current.log_level.filter());
+            app.manage(settings);
+
+            // Сессия восстанавливается из хранилища учётных данных, поэтому повторный логин не нужен
+            let data_dir = app.path().app_data_dir()?;
+            let store = credentials::open(&data_dir, current.backend.profile);
+            log::info!("credential store: {}, backend profile: {}", store.name(), current.backend.profile.name());
+            let expired_handle = app.handle().clone();
+            let profile = current.backend.active();
+            let client = SkygenClient::from_profile(&profile.url, profile.wss_url)
+                .with_store(store)
+                .on_session_expired(Arc::new(move || {
+                    let _ = expired_handle.emit("auth://expired", ());
+                }));
+            app.manage(SkygenState::new(client));
+
+            // Бекенды действий определяются один раз: набор утилит и сессия не меняются на лету
+            let executors = Arc::new(ExecutorRegistry::detect());
+            app.manage(executors.clone());
+            // Проверка Python запускает интерпретатор: окна, трей и хоткеи её не ждут, бекенд добавится по готовности
+            if let Ok(script) = get_python_path() {
+                tauri::async_runtime::spawn_blocking(move || executors.detect_python(script));
+            }
 
-            // Глобальный хоткей: Option+Space — переключение оверлея
-            #[cfg(target_os = "macos")]
-            {
-                use tauri_plugin_global_shortcut::GlobalShortcutExt;
-                let handle = app.handle();
-                handle.plugin(tauri_plugin_global_shortcut::Builder::new().build())?;
-                handle.global_shortcut().on_shortcut("Option+Space", |app, _shortcut, _event| {
-                    let state: tauri::State<'_, Mutex<OverlayState>> = app.state();
-                    let visible_now = {
-                        let guard = state.lock().unwrap();
-                        guard.visible
-                    };
-                    if visible_now {
-                        let _ = hide_overlay(app.clone(), state);
-                    } else {
-                        let _ = show_overlay(app.clone(), state);
+            // Рискованные действия подтверждаются в окне оверлея
+            let prompt_handle = app.handle().clone();
+            let policy = Policy::load(data_dir.join("policy.json")).on_prompt(Arc::new(move |request| {
+                let _ = prompt_handle.emit(APPROVAL_EVENT, request);
+                let app = prompt_handle.clone();
+                tauri::async_runtime::spawn_blocking(move || {
+                    if let Err(e) = app.state::<OverlayState>().lock().unwrap().show(&app) {
+                        log::warn!("failed to show approval prompt: {}", e);
                     }
-                })?;
+                });
+            }));
+            app.manage(Arc::new(policy));
+            app.manage(Arc::new(AuditLog::new(data_dir.join("audit").join("audit.jsonl"))));
+            app.manage(Arc::new(ModeDriver::spawn(app.handle().clone())));
+
+            if let Err(e) = apply_settings(app.handle(), &current) {
+                log::warn!("failed to apply settings: {}", e);
             }
 
-            // Обработка закрытия приложения - убиваем outline процесс
-            {
-                let app_handle = app.handle().clone();
-                let app_handle_clone = app_handle.clone();
+            // Панель создаётся заранее скрытой: показ по хоткею не ждёт загрузки страницы
+            if let Err(e) = app.state::<OverlayState>().lock().unwrap().create(app.handle()) {
+                log::warn!("failed to create overlay window: {}", e);
+            }
 
-                app_handle.listen("tauri://close-requested", move |_event| {
-                    let state: tauri::State<'_, Mutex<OverlayState>> = app_handle_clone.state();
-                    let mut s = state.lock().unwrap();
+            tray::create(app.handle())?;
 
-                    // Закрываем stdin, если открыт
-                    s.outline_stdin.take();
+            // Ссылки skygen://; при запуске по ссылке обработчик вызывается сразу с ней
+            app.handle().plugin(tauri_plugin_deep_link::init())?;
+            #[cfg(any(target_os = "linux", all(debug_assertions, windows)))]
+            if let Err(e) = app.deep_link().register_all() {
+                log::warn!("failed to register {}:// scheme: {}", deep_link::SCHEME, e);
+            }
+            let link_handle = app.handle().clone();
+            app.deep_link().on_open_url(move |event| {
+                for url in event.urls() {
+                    deep_link::handle(&link_handle, url.as_str());
+                }
+            });
 
-                    // Завершаем процесс, если он есть
-                    if let Some(mut child) = s.outline_process.take() {
-                        let _ = child.kill();
-                    }
+            // Обновления: подпись проверяется встроенным ключом, установка при перезапуске или выходе.
+            // Ключ и фид канала подставляет updater.rs; пустой plugins.updater в tauri.conf.json нужен только
+            // для запуска плагина, без него конфиг плагина не разбирается и setup падает
+            app.handle().plugin(tauri_plugin_updater::Builder::new().build())?;
+            updater::spawn(app.handle().clone());
 
-                    // macOS: добиваем helper через pkill
-                    #[cfg(target_os = "macos")]
-                    {
-                        let _ = std::process::Command::new("/usr/bin/pkill")
-                            .arg("-f")
-                            .arg("OutlineHelper")
-                            .status();
+            // При входе в систему приложение поднимается без окна: только трей и агент, если устройство уже зарегистрировано
+            app.handle().plugin(autostart::plugin())?;
+            if let Err(e) = autostart::sync(app.handle(), current.autostart) {
+                log::warn!("failed to sync autostart: {}", e);
+            }
+            if autostart::in_background() {
+                log::info!("started in background mode");
+                let app = app.handle().clone();
+                tauri::async_runtime::spawn(async move {
+                    let agent = app.state::<Agent>();
+                    match start_agent(&app, &app.state::<SkygenState>(), &agent).await {
+                        Ok(()) => {}
+                        Err(AppError::DeviceNotEnrolled) => log::info!("device is not enrolled, agent stays stopped"),
+                        Err(e) => log::warn!("failed to start agent in background: {}", e),
                     }
                 });
+            } else {
+                open_main_window(app.handle())?;
             }
 
-            // Логирование только в debug режиме
-            if cfg!(debug_assertions) {
-                app.handle().plugin(
-                    tauri_plugin_log::Builder::default()
-                        .level(log::LevelFilter::Info)
-                        .build(),
-                )?;
-            }
+            // Глобальные хоткеи на всех десктопных платформах; сочетания настраиваются из UI
+            app.handle().plugin(tauri_plugin_global_shortcut::Builder::new().build())?;
+            let shortcuts = Shortcuts::new(&current.shortcuts);
+            shortcuts.register_all(app.handle());
+            app.manage(shortcuts);
 
             Ok(())
         })
@@ -0,0 +1,241 @@
+// Журнал приложения: JSON-строки в каталоге логов с ротацией по размеру, stderr дочерних процессов и архив для поддержки
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_log::{fern, Target, TargetKind};
+use tokio::io::{AsyncBufReadExt, AsyncRead};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::error::AppError;
+use crate::settings::SettingsStore;
+
+const FILE_NAME: &str = "skygen";
+const MAX_FILE_SIZE: u64 = 5 * 1024 * 1024;
+/// Сколько файлов хранится вместе с текущим: skygen.log, skygen.1.log … skygen.4.log
+const KEEP_FILES: usize = 5;
+
+/// Подключает логгер; вызывается первым в setup, чтобы в файл попал весь запуск.
+/// Уровень фильтрует log::set_max_level из настроек, сам логгер пропускает всё.
+pub fn init(app: &AppHandle) -> Result<(), AppError> {
+    let file = RotatingFile::open(app.path().app_log_dir()?, MAX_FILE_SIZE)?;
+    let json = fern::Dispatch::new()
+        .format(|out, message, record| {
+            let line = serde_json::json!({
+                "ts": chrono::Local::now().to_rfc3339(),
+                "level": record.level().as_str(),
+                "target": record.target(),
+                "message": message.to_string(),
+            });
+            out.finish(format_args!("{}", line))
+        })
+        .chain(Box::new(file) as Box<dyn Write + Send>);
+    let console = fern::Dispatch::new()
+        .format(|out, message, record| {
+            out.finish(format_args!("{} {:<5} {} {}", chrono::Local::now().format("%H:%M:%S%.3f"), record.level(), record.target(), message))
+        })
+        .chain(io::stdout());
+
+    app.plugin(
+        tauri_plugin_log::Builder::new()
+            .level(log::LevelFilter::Trace)
+            // HTTP-стек на trace пишет каждый кадр и заглушил бы всё остальное
+            .level_for("hyper", log::LevelFilter::Info)
+            .level_for("hyper_util", log::LevelFilter::Info)
+            .level_for("rustls", log::LevelFilter::Info)
+            .level_for("tungstenite", log::LevelFilter::Info)
+            .level_for("tokio_tungstenite", log::LevelFilter::Info)
+            .format(|out, message, _| out.finish(format_args!("{}", message)))
+            .clear_targets()
+            .targets([Target::new(TargetKind::Dispatch(json)), Target::new(TargetKind::Dispatch(console))])
+            .build(),
+    )?;
+    Ok(())
+}
+
+/// Файл журнала, который при превышении размера сдвигается в skygen.1.log, а самый старый удаляется
+struct RotatingFile {
+    dir: PathBuf,
+    file: File,
+    size: u64,
+    max_size: u64,
+    // Строка приходит несколькими write, файл меняем только между строками
+    line_start: bool,
+}
+
+impl RotatingFile {
+    fn open(dir: PathBuf, max_size: u64) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+        let file = OpenOptions::new().create(true).append(true).open(file_path(&dir, 0))?;
+        let size = file.metadata()?.len();
+        Ok(Self { dir, file, size, max_size, line_start: true })
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        for index in (1..KEEP_FILES).rev() {
+            let from = file_path(&self.dir, index - 1);
+            if from.exists() {
+                fs::rename(from, file_path(&self.dir, index))?;
+            }
+        }
+        self.file = OpenOptions::new().create(true).append(true).open(file_path(&self.dir, 0))?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.line_start && self.size > 0 && self.size + buf.len() as u64 > self.max_size {
+            // Не удалось сдвинуть файлы (например, их держит антивирус) — пишем дальше в текущий
+            let _ = self.rotate();
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        if written > 0 {
+            self.line_start = buf[written - 1] == b'\n';
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+fn file_path(dir: &Path, index: usize) -> PathBuf {
+    match index {
+        0 => dir.join(format!("{}.log", FILE_NAME)),
+        _ => dir.join(format!("{}.{}.log", FILE_NAME, index)),
+    }
+}
+
+/// Пересылает stderr дочернего процесса в журнал построчно; поток завершается вместе с процессом
+pub fn forward_stderr<R: Read + Send + 'static>(target: &'static str, stderr: R) {
+    std::thread::spawn(move || {
+        for line in BufReader::new(stderr).lines() {
+            let Ok(line) = line else { break };
+            log::info!(target: target, "{}", line);
+        }
+    });
+}
+
+/// То же для процессов tokio
+pub fn forward_stderr_async<R: AsyncRead + Unpin + Send + 'static>(target: &'static str, stderr: R) {
+    tokio::spawn(async move {
+        let mut lines = tokio::io::BufReader::new(stderr).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            log::info!(target: target, "{}", line);
+        }
+    });
+}
+
+/// Собирает журналы, настройки и сведения о системе в zip; возвращает число файлов в архиве
+pub fn export(app: &AppHandle, destination: &Path) -> Result<usize, AppError> {
+    log::logger().flush();
+    let failed = |e: zip::result::ZipError| AppError::LogExport(e.to_string());
+
+    // Архив собирается рядом и переименовывается в конце, чтобы не оставить обрезанный zip
+    let tmp = destination.with_extension("zip.tmp");
+    let mut zip = ZipWriter::new(File::create(&tmp)?);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut count = 0;
+
+    let mut logs: Vec<PathBuf> = fs::read_dir(app.path().app_log_dir()?)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "log"))
+        .collect();
+    logs.sort();
+    for path in logs {
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else { continue };
+        zip.start_file(format!("logs/{}", name), options).map_err(failed)?;
+        io::copy(&mut File::open(&path)?, &mut zip)?;
+        count += 1;
+    }
+
+    // В настройках только адреса и параметры интерфейса; учётные данные хранятся отдельно и сюда не попадают
+    zip.start_file("settings.json", options).map_err(failed)?;
+    zip.write_all(&serde_json::to_vec_pretty(&app.state::<SettingsStore>().get()).map_err(|e| AppError::LogExport(e.to_string()))?)?;
+    count += 1;
+
+    let system = serde_json::json!({
+        "version": app.package_info().version.to_string(),
+        "os": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+        "exported_at": chrono::Local::now().to_rfc3339(),
+    });
+    zip.start_file("system.json", options).map_err(failed)?;
+    zip.write_all(system.to_string().as_bytes())?;
+    count += 1;
+
+    zip.finish().map_err(failed)?;
+    fs::rename(&tmp, destination)?;
+    log::info!("exported {} files to {}", count, destination.display());
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMIT: u64 = 20;
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("skygen-logs-{}", uuid::Uuid::new_v4()))
+    }
+
+    fn read(dir: &Path, index: usize) -> String {
+        fs::read_to_string(file_path(dir, index)).unwrap()
+    }
+
+    #[test]
+    fn rotates_only_past_the_limit_and_between_lines() {
+        let dir = temp_dir();
+        let mut file = RotatingFile::open(dir.clone(), LIMIT).unwrap();
+
+        // Ровно LIMIT байт ещё помещаются в текущий файл
+        file.write_all(b"0123456789\n").unwrap();
+        file.write_all(b"abcdefgh\n").unwrap();
+        assert!(!file_path(&dir, 1).exists());
+
+        file.write_all(b"x\n").unwrap();
+        assert_eq!(read(&dir, 1), "0123456789\nabcdefgh\n");
+        assert_eq!(read(&dir, 0), "x\n");
+
+        // Строка, пришедшая несколькими write, не разрывается между файлами
+        file.write_all(b"yyyyyyyyyyyyyyyyy").unwrap();
+        file.write_all(b"zz\n").unwrap();
+        assert_eq!(read(&dir, 0), "x\nyyyyyyyyyyyyyyyyyzz\n");
+        assert_eq!(read(&dir, 1), "0123456789\nabcdefgh\n");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn keeps_a_limited_number_of_files() {
+        let dir = temp_dir();
+        let mut file = RotatingFile::open(dir.clone(), LIMIT).unwrap();
+        for line in 0..KEEP_FILES + 2 {
+            file.write_all(format!("line {:<14}\n", line).as_bytes()).unwrap();
+        }
+
+        assert_eq!(read(&dir, 0).trim_end(), format!("line {}", KEEP_FILES + 1));
+        assert_eq!(read(&dir, KEEP_FILES - 1).trim_end(), "line 2");
+        assert!(!file_path(&dir, KEEP_FILES).exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn reopened_file_counts_existing_size() {
+        let dir = temp_dir();
+        RotatingFile::open(dir.clone(), LIMIT).unwrap().write_all(b"0123456789012345\n").unwrap();
+
+        let mut file = RotatingFile::open(dir.clone(), LIMIT).unwrap();
+        file.write_all(b"next\n").unwrap();
+        assert_eq!(read(&dir, 0), "next\n");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,259 @@
+// macOS: рамка через отдельно собранный OutlineHelper (Swift).
+// Протокол по stdio, JSON-строки: helper сообщает {"type":"ready"} после показа окна
+// и {"type":"ack","id":N} на каждое обновление стиля; закрытие stdin — сигнал завершиться.
+use serde::Deserialize;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{ChildStdin, Command, Stdio};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use super::OutlineStyle;
+use crate::error::AppError;
+use crate::logging;
+
+const READY_TIMEOUT: Duration = Duration::from_secs(5);
+const ACK_TIMEOUT: Duration = Duration::from_secs(2);
+const STOP_TIMEOUT: Duration = Duration::from_secs(1);
+/// Столько перезапусков подряд без успешного ready, потом супервизор сдаётся
+const MAX_RESTARTS: u32 = 3;
+
+/// Helper перестал работать сам по себе и перезапуски не помогли
+pub const ERROR_EVENT: &str = "outline://error";
+
+pub type ErrorHook = Arc<dyn Fn(String) + Send + Sync>;
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum HelperMessage {
+    Ready,
+    Ack { id: u64 },
+    Error { message: String },
+}
+
+#[derive(Default)]
+struct Shared {
+    style: OutlineStyle,
+    stdin: Option<ChildStdin>,
+    pgid: Option<i32>,
+    generation: u64,
+    ready: bool,
+    acked: u64,
+    stopping: bool,
+    failure: Option<String>,
+}
+
+type SharedState = Arc<(Mutex<Shared>, Condvar)>;
+
+pub struct OutlineHelper {
+    shared: SharedState,
+    next_id: u64,
+    supervisor: Option<JoinHandle<()>>,
+}
+
+/// OUTLINE_HELPER_PATH, затем сборка в src-tauri/macos/OutlineHelper
+pub fn find() -> Option<PathBuf> {
+    if let Ok(explicit) = std::env::var("OUTLINE_HELPER_PATH") {
+        return Some(PathBuf::from(explicit));
+    }
+
+    let mut candidates: Vec<PathBuf> = Vec::new();
+    // Относительно рабочей директории (обычно src-tauri)
+    if let Ok(cwd) = std::env::current_dir() {
+        candidates.push(cwd.join("macos/OutlineHelper/OutlineHelper"));
+    }
+    // Относительно корня проекта (поднимаемся на три уровня из exe)
+    if let Ok(exe) = std::env::current_exe() {
+        if let Some(root) = exe.parent().and_then(|p| p.parent()).and_then(|p| p.parent()) {
+            candidates.push(root.join("src-tauri/macos/OutlineHelper/OutlineHelper"));
+        }
+    }
+    candidates.into_iter().find(|p| p.exists())
+}
+
+impl OutlineHelper {
+    /// Запускает супервизор и ждёт, пока helper покажет окно
+    pub fn spawn(path: &Path, style: &OutlineStyle, on_error: ErrorHook) -> Result<Self, AppError> {
+        let shared: SharedState = Arc::new((Mutex::new(Shared { style: style.clone(), ..Shared::default() }), Condvar::new()));
+        let supervisor = {
+            let shared = shared.clone();
+            let path = path.to_path_buf();
+            std::thread::spawn(move || supervise(&path, &shared, &on_error))
+        };
+        let mut helper = Self { shared, next_id: 0, supervisor: Some(supervisor) };
+
+        let result = helper.wait(READY_TIMEOUT, |s| s.ready);
+        if let Err(e) = result {
+            helper.shutdown();
+            return Err(e);
+        }
+        Ok(helper)
+    }
+
+    /// Отправляет стиль и ждёт подтверждения. Если helper упал, супервизор перезапустит его уже с новым стилем.
+    pub fn apply(&mut self, style: &OutlineStyle) -> Result<(), AppError> {
+        self.next_id += 1;
+        let id = self.next_id;
+        let generation = {
+            let (lock, _) = &*self.shared;
+            let mut shared = lock.lock().unwrap();
+            if let Some(failure) = &shared.failure {
+                return Err(AppError::HelperFailed(failure.clone()));
+            }
+            shared.style = style.clone();
+            let mut message = serde_json::to_value(style).map_err(|e| AppError::Internal(e.to_string()))?;
+            message["id"] = id.into();
+            let stdin = shared.stdin.as_mut().ok_or(AppError::HelperNotRunning)?;
+            // Ошибка записи означает, что процесс уже умер: дальше ждём перезапуска
+            let _ = writeln!(stdin, "{}", message).and_then(|_| stdin.flush());
+            shared.generation
+        };
+
+        self.wait(ACK_TIMEOUT, |s| s.acked >= id || (s.generation != generation && s.ready))
+    }
+
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        let pgid = {
+            let (lock, _) = &*self.shared;
+            let mut shared = lock.lock().unwrap();
+            shared.stopping = true;
+            // EOF на stdin — штатный сигнал завершения
+            shared.stdin.take();
+            shared.pgid
+        };
+
+        if let Some(supervisor) = self.supervisor.take() {
+            let deadline = Instant::now() + STOP_TIMEOUT;
+            while !supervisor.is_finished() && Instant::now() < deadline {
+                std::thread::sleep(Duration::from_millis(20));
+            }
+            if !supervisor.is_finished() {
+                // Группа создаётся при запуске, поэтому сигнал получат только helper и его потомки
+                if let Some(pgid) = pgid {
+                    unsafe { libc::killpg(pgid, libc::SIGKILL) };
+                }
+            }
+            let _ = supervisor.join();
+        }
+    }
+
+    fn wait(&self, timeout: Duration, done: impl Fn(&Shared) -> bool) -> Result<(), AppError> {
+        let (lock, ready) = &*self.shared;
+        let deadline = Instant::now() + timeout;
+        let mut shared: MutexGuard<'_, Shared> = lock.lock().unwrap();
+        loop {
+            if done(&shared) {
+                return Ok(());
+            }
+            if let Some(failure) = &shared.failure {
+                return Err(AppError::HelperFailed(failure.clone()));
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(AppError::HelperFailed("OutlineHelper did not respond in time".into()));
+            }
+            shared = ready.wait_timeout(shared, deadline - now).unwrap().0;
+        }
+    }
+}
+
+impl Drop for OutlineHelper {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+fn supervise(path: &Path, shared: &SharedState, on_error: &ErrorHook) {
+    let (lock, changed) = &**shared;
+    let mut restarts = 0;
+    loop {
+        let style = lock.lock().unwrap().style.clone();
+        let mut child = match Command::new(path)
+            .arg("--color")
+            .arg(&style.color)
+            .arg("--width")
+            .arg(style.width.to_string())
+            .arg("--blur")
+            .arg(style.blur.to_string())
+            .arg("--animation")
+            .arg(style.animation.as_str())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .process_group(0)
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(e) => {
+                let mut s = lock.lock().unwrap();
+                s.failure = Some(match e.kind() {
+                    std::io::ErrorKind::NotFound => format!("OutlineHelper not found at {}", path.display()),
+                    _ => format!("Failed to start OutlineHelper: {}", e),
+                });
+                changed.notify_all();
+                return;
+            }
+        };
+
+        let stdout = child.stdout.take().expect("stdout is piped");
+        logging::forward_stderr("outline_helper", child.stderr.take().expect("stderr is piped"));
+        {
+            let mut s = lock.lock().unwrap();
+            s.stdin = child.stdin.take();
+            s.pgid = Some(child.id() as i32);
+            s.generation += 1;
+            s.ready = false;
+            if s.stopping {
+                s.stdin.take();
+            }
+        }
+
+        let mut was_ready = false;
+        for line in BufReader::new(stdout).lines() {
+            let Ok(line) = line else { break };
+            let Ok(message) = serde_json::from_str::<HelperMessage>(&line) else {
+                log::debug!("OutlineHelper: {}", line);
+                continue;
+            };
+            let mut s = lock.lock().unwrap();
+            match message {
+                HelperMessage::Ready => {
+                    s.ready = true;
+                    was_ready = true;
+                }
+                HelperMessage::Ack { id } => s.acked = s.acked.max(id),
+                HelperMessage::Error { message } => log::warn!("OutlineHelper error: {}", message),
+            }
+            changed.notify_all();
+        }
+
+        let status = child.wait();
+        let mut s = lock.lock().unwrap();
+        s.stdin = None;
+        s.pgid = None;
+        s.ready = false;
+        if s.stopping {
+            changed.notify_all();
+            return;
+        }
+
+        restarts = if was_ready { 1 } else { restarts + 1 };
+        log::warn!("OutlineHelper exited unexpectedly ({:?}), restart {}/{}", status, restarts, MAX_RESTARTS);
+        if restarts > MAX_RESTARTS {
+            let failure = "OutlineHelper keeps crashing".to_string();
+            s.failure = Some(failure.clone());
+            changed.notify_all();
+            drop(s);
+            on_error(failure);
+            return;
+        }
+        drop(s);
+        std::thread::sleep(Duration::from_millis(200 * restarts as u64));
+    }
+}
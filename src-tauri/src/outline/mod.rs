@@ -0,0 +1,266 @@
+// Рамка "агент управляет экраном": прозрачные окна поверх всего на каждом мониторе, клики проходят насквозь
+use serde::{Deserialize, Serialize};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::error::AppError;
+
+#[cfg(target_os = "macos")]
+mod helper;
+mod window;
+
+/// Окна рамки называются outline_<индекс монитора>
+pub const OUTLINE_WINDOW_PREFIX: &str = "outline_";
+pub const STYLE_EVENT: &str = "outline://style";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutlineAnimation {
+    #[default]
+    None,
+    Pulse,
+    Flash,
+}
+
+impl OutlineAnimation {
+    fn as_str(self) -> &'static str {
+        match self {
+            OutlineAnimation::None => "none",
+            OutlineAnimation::Pulse => "pulse",
+            OutlineAnimation::Flash => "flash",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OutlineStyle {
+    pub color: String,
+    pub width: u32,
+    pub blur: u32,
+    pub animation: OutlineAnimation,
+}
+
+impl Default for OutlineStyle {
+    fn default() -> Self {
+        Self { color: "#FF4D4F".into(), width: 4, blur: 0, animation: OutlineAnimation::None }
+    }
+}
+
+impl OutlineStyle {
+    fn merge(&mut self, color: Option<String>, width: Option<u32>, blur: Option<u32>) {
+        if let Some(color) = color {
+            self.color = color;
+        }
+        if let Some(width) = width {
+            self.width = width;
+        }
+        if let Some(blur) = blur {
+            self.blur = blur;
+        }
+    }
+}
+
+/// Пресеты рамки по состоянию агента. Manual — стиль, заданный через start_outline/update_outline.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutlineMode {
+    #[default]
+    Off,
+    Manual,
+    Working,
+    AwaitingApproval,
+    Error,
+    /// Аварийная остановка агента
+    Alert,
+}
+
+impl OutlineMode {
+    fn preset(self) -> Option<OutlineStyle> {
+        let (color, animation) = match self {
+            OutlineMode::Off | OutlineMode::Manual => return None,
+            OutlineMode::Working => ("#4F8CFF", OutlineAnimation::Pulse),
+            OutlineMode::AwaitingApproval => ("#FFB020", OutlineAnimation::None),
+            OutlineMode::Error => ("#FF4D4F", OutlineAnimation::Flash),
+            OutlineMode::Alert => ("#FF0000", OutlineAnimation::Pulse),
+        };
+        let width = if self == OutlineMode::Alert { 10 } else { 6 };
+        Some(OutlineStyle { color: color.into(), width, blur: 12, animation })
+    }
+
+    /// Сколько держатся временные режимы, прежде чем рамка погаснет
+    fn linger(self) -> Option<Duration> {
+        match self {
+            OutlineMode::Error => Some(Duration::from_millis(1500)),
+            OutlineMode::Alert => Some(Duration::from_secs(5)),
+            _ => None,
+        }
+    }
+}
+
+enum Renderer {
+    Window(window::OutlineWindow),
+    #[cfg(target_os = "macos")]
+    Helper(helper::OutlineHelper),
+}
+
+#[derive(Default)]
+pub struct Outline {
+    /// Стиль из настроек: от него считаются start без width/blur и показ по хоткею
+    base: OutlineStyle,
+    style: OutlineStyle,
+    mode: OutlineMode,
+    renderer: Option<Renderer>,
+}
+
+pub type OutlineState = Mutex<Outline>;
+
+impl Outline {
+    pub fn mode(&self) -> OutlineMode {
+        self.mode
+    }
+
+    pub fn start(&mut self, app: &AppHandle, color: String, width: Option<u32>, blur: Option<u32>) -> Result<(), AppError> {
+        self.stop(app);
+        self.style = self.base.clone();
+        self.style.merge(Some(color), width, blur);
+        self.mode = OutlineMode::Manual;
+        self.show(app)
+    }
+
+    /// Новый стиль по умолчанию; рамка, запущенная вручную, перерисовывается сразу
+    pub fn configure(&mut self, color: String, width: u32, blur: u32) -> Result<(), AppError> {
+        self.base = OutlineStyle { color, width, blur, animation: OutlineAnimation::None };
+        if self.mode == OutlineMode::Manual && self.renderer.is_some() {
+            self.style = self.base.clone();
+            return self.apply();
+        }
+        Ok(())
+    }
+
+    pub fn update(&mut self, color: Option<String>, width: Option<u32>, blur: Option<u32>) -> Result<(), AppError> {
+        if self.renderer.is_none() {
+            return Err(AppError::HelperNotRunning);
+        }
+        self.style.merge(color, width, blur);
+        self.mode = OutlineMode::Manual;
+        self.apply()
+    }
+
+    /// Хоткей: прячет рамку или показывает её со стилем из настроек
+    pub fn toggle(&mut self, app: &AppHandle) -> Result<(), AppError> {
+        if self.renderer.is_some() {
+            self.stop(app);
+            Ok(())
+        } else {
+            let color = self.base.color.clone();
+            self.start(app, color, None, None)
+        }
+    }
+
+    /// Переключает пресет; запускает рамку, если она не была показана
+    pub fn set_mode(&mut self, app: &AppHandle, mode: OutlineMode) -> Result<(), AppError> {
+        self.mode = mode;
+        match mode.preset() {
+            Some(style) => {
+                self.style = style;
+                if self.renderer.is_some() {
+                    self.apply()
+                } else {
+                    self.show(app)
+                }
+            }
+            None if mode == OutlineMode::Off => {
+                self.stop(app);
+                Ok(())
+            }
+            // Manual только фиксирует текущий стиль от автоматических переключений
+            None if self.renderer.is_none() => Err(AppError::HelperNotRunning),
+            None => Ok(()),
+        }
+    }
+
+    /// На macOS собранный OutlineHelper предпочтительнее: он встаёт над полноэкранными приложениями
+    fn show(&mut self, app: &AppHandle) -> Result<(), AppError> {
+        #[cfg(target_os = "macos")]
+        if let Some(path) = helper::find() {
+            let error_handle = app.clone();
+            let on_error: helper::ErrorHook = std::sync::Arc::new(move |error| {
+                let _ = tauri::Emitter::emit(&error_handle, helper::ERROR_EVENT, error);
+            });
+            self.renderer = Some(Renderer::Helper(helper::OutlineHelper::spawn(&path, &self.style, on_error)?));
+            return Ok(());
+        }
+
+        self.renderer = Some(Renderer::Window(window::OutlineWindow::open(app, &self.style)?));
+        Ok(())
+    }
+
+    fn apply(&mut self) -> Result<(), AppError> {
+        match self.renderer.as_mut().ok_or(AppError::HelperNotRunning)? {
+            Renderer::Window(window) => window.apply(&self.style),
+            #[cfg(target_os = "macos")]
+            Renderer::Helper(helper) => helper.apply(&self.style),
+        }
+    }
+
+    pub fn stop(&mut self, app: &AppHandle) {
+        self.mode = OutlineMode::Off;
+        match self.renderer.take() {
+            Some(Renderer::Window(window)) => window.close(),
+            #[cfg(target_os = "macos")]
+            Some(Renderer::Helper(helper)) => helper.stop(),
+            None => {}
+        }
+        // Окна могли остаться от прошлого запуска, если его закрыли не через stop
+        for (label, window) in app.webview_windows() {
+            if label.starts_with(OUTLINE_WINDOW_PREFIX) {
+                let _ = window.close();
+            }
+        }
+    }
+}
+
+/// Фоновое переключение режимов по событиям агента: запросы сворачиваются до последнего,
+/// чтобы быстрые задачи не выстраивали очередь из перерисовок, а вспышка ошибки и тревога гаснут сами
+pub struct ModeDriver {
+    requests: Mutex<Sender<OutlineMode>>,
+}
+
+impl ModeDriver {
+    pub fn spawn(app: AppHandle) -> Self {
+        let (requests, incoming) = mpsc::channel::<OutlineMode>();
+        std::thread::spawn(move || {
+            let mut pending = incoming.recv().ok();
+            while let Some(requested) = pending.take() {
+                let mode = incoming.try_iter().last().unwrap_or(requested);
+                let state = app.state::<OutlineState>();
+                {
+                    let mut outline = state.lock().unwrap();
+                    // Рамку, которую пользователь настроил руками, автоматически не трогаем, кроме тревоги
+                    if outline.mode() != OutlineMode::Manual || mode == OutlineMode::Alert {
+                        if let Err(e) = outline.set_mode(&app, mode) {
+                            log::warn!("failed to switch outline to {:?}: {}", mode, e);
+                        }
+                    }
+                }
+
+                pending = if let Some(linger) = mode.linger() {
+                    match incoming.recv_timeout(linger) {
+                        Ok(next) => Some(next),
+                        Err(RecvTimeoutError::Timeout) => Some(OutlineMode::Off),
+                        Err(RecvTimeoutError::Disconnected) => None,
+                    }
+                } else {
+                    incoming.recv().ok()
+                };
+            }
+        });
+        Self { requests: Mutex::new(requests) }
+    }
+
+    pub fn request(&self, mode: OutlineMode) {
+        let _ = self.requests.lock().unwrap().send(mode);
+    }
+}
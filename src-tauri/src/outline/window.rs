@@ -0,0 +1,162 @@
+// Рамка как Tauri-окна по одному на каждый монитор; на Wayland — через layer-shell
+use tauri::{AppHandle, Emitter, Monitor, WebviewUrl, WebviewWindow, WebviewWindowBuilder};
+
+use super::{OutlineStyle, OUTLINE_WINDOW_PREFIX, STYLE_EVENT};
+use crate::display;
+use crate::error::AppError;
+
+/// Рамка должна быть видна на любом мониторе, куда смотрит пользователь: окно на каждый,
+/// как и у затемнения оверлея, потому что одно окно на весь стол не переживает разный масштаб
+pub struct OutlineWindow {
+    windows: Vec<WebviewWindow>,
+}
+
+impl OutlineWindow {
+    pub fn open(app: &AppHandle, style: &OutlineStyle) -> Result<Self, AppError> {
+        #[cfg(all(target_os = "linux", not(feature = "layer-shell")))]
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            log::warn!("built without layer-shell, outline on Wayland may be covered by other windows");
+        }
+
+        // Рамка на части мониторов лучше, чем никакой: ошибка возвращается, только если не открылось ни одно окно
+        let mut windows = Vec::new();
+        let mut error = None;
+        for (index, monitor) in display::all(app)?.iter().enumerate() {
+            match open_one(app, style, index, monitor) {
+                Ok(window) => windows.push(window),
+                Err(e) => {
+                    log::warn!("failed to open outline on monitor {}: {}", index, e);
+                    error = Some(e);
+                }
+            }
+        }
+        match error {
+            Some(e) if windows.is_empty() => Err(e),
+            _ => Ok(Self { windows }),
+        }
+    }
+
+    pub fn apply(&self, style: &OutlineStyle) -> Result<(), AppError> {
+        for window in &self.windows {
+            window.emit_to(window.label(), STYLE_EVENT, style)?;
+        }
+        Ok(())
+    }
+
+    pub fn close(self) {
+        for window in self.windows {
+            let _ = window.close();
+        }
+    }
+}
+
+fn open_one(app: &AppHandle, style: &OutlineStyle, index: usize, monitor: &Monitor) -> Result<WebviewWindow, AppError> {
+    let scale = monitor.scale_factor();
+    let position = monitor.position().to_logical::<f64>(scale);
+    let size = monitor.size().to_logical::<f64>(scale);
+
+    // Стиль передаётся в URL, чтобы первый кадр уже был нужного цвета
+    let url = format!(
+        "overlay/outline?color={}&width={}&blur={}&animation={}",
+        style.color.trim_start_matches('#'),
+        style.width,
+        style.blur,
+        style.animation.as_str()
+    );
+    let label = format!("{}{}", OUTLINE_WINDOW_PREFIX, index);
+    let window = WebviewWindowBuilder::new(app, label, WebviewUrl::App(url.into()))
+        .title("Outline")
+        .position(position.x, position.y)
+        .inner_size(size.width, size.height)
+        .resizable(false)
+        .decorations(false)
+        .transparent(true)
+        .shadow(false)
+        .always_on_top(true)
+        .visible_on_all_workspaces(true)
+        .skip_taskbar(true)
+        .focused(false)
+        .visible(false)
+        .build()?;
+    window.set_ignore_cursor_events(true)?;
+
+    #[cfg(all(target_os = "linux", feature = "layer-shell"))]
+    {
+        // GTK трогаем только с главного потока; show идёт после настройки layer-shell
+        let shell_window = window.clone();
+        app.run_on_main_thread(move || {
+            if let Err(e) = layer_shell::attach(&shell_window, index) {
+                log::warn!("layer-shell is unavailable for outline: {}", e);
+            }
+            let _ = shell_window.show();
+        })?;
+    }
+    #[cfg(not(all(target_os = "linux", feature = "layer-shell")))]
+    window.show()?;
+
+    #[cfg(target_os = "macos")]
+    raise_above_fullscreen(app, &window);
+
+    Ok(window)
+}
+
+#[cfg(target_os = "macos")]
+fn raise_above_fullscreen(app: &AppHandle, window: &WebviewWindow) {
+    use cocoa::appkit::NSWindowCollectionBehavior;
+    use cocoa::base::{id, NO};
+    use objc::{msg_send, sel, sel_impl};
+
+    let window = window.clone();
+    let _ = app.run_on_main_thread(move || unsafe {
+        if let Ok(ns_win_ptr) = window.ns_window() {
+            let ns_win: id = ns_win_ptr as id;
+            let level: i32 = 1000; // уровень screen saver, как у OutlineHelper
+            let _: () = msg_send![ns_win, setLevel: level];
+            let behavior = NSWindowCollectionBehavior::NSWindowCollectionBehaviorCanJoinAllSpaces
+                | NSWindowCollectionBehavior::NSWindowCollectionBehaviorFullScreenAuxiliary
+                | NSWindowCollectionBehavior::NSWindowCollectionBehaviorTransient;
+            let _: () = msg_send![ns_win, setCollectionBehavior: behavior];
+            let _: () = msg_send![ns_win, setOpaque: NO];
+        }
+    });
+}
+
+#[cfg(all(target_os = "linux", feature = "layer-shell"))]
+mod layer_shell {
+    use gtk::prelude::*;
+    use gtk_layer_shell::{Edge, KeyboardMode, Layer, LayerShell};
+    use tauri::WebviewWindow;
+
+    /// Обычные окна Wayland не могут быть "поверх всех" и сами выбирать позицию,
+    /// поэтому рамка становится overlay-слоем, прикреплённым ко всем краям своего монитора
+    pub fn attach(window: &WebviewWindow, monitor: usize) -> Result<(), String> {
+        if std::env::var_os("WAYLAND_DISPLAY").is_none() {
+            return Ok(());
+        }
+        if !gtk_layer_shell::is_supported() {
+            return Err("compositor does not support wlr-layer-shell".into());
+        }
+        let gtk_window = window.gtk_window().map_err(|e| e.to_string())?;
+        // layer-shell включается только до того, как окно реализовано, а tao реализует его ещё в build().
+        // Вызвать init_layer_shell раньше нельзя: GtkWindow создаётся внутри Tauri. Пересоздание безопасно,
+        // потому что окно построено с visible(false) и ещё ни разу не показывалось: unrealize удаляет только
+        // GdkWindow и wl_surface, без видимого мигания, а форма ввода от set_ignore_cursor_events хранится
+        // в виджете и снова применяется GTK при реализации в show()
+        if gtk_window.is_realized() {
+            gtk_window.unrealize();
+        }
+        gtk_window.init_layer_shell();
+        gtk_window.set_layer(Layer::Overlay);
+        gtk_window.set_namespace("skygen-outline");
+        // tao перечисляет мониторы в порядке GDK, поэтому индексы совпадают
+        if let Some(monitor) = gtk_window.display().monitor(monitor as i32) {
+            gtk_window.set_monitor(&monitor);
+        }
+        for edge in [Edge::Top, Edge::Bottom, Edge::Left, Edge::Right] {
+            gtk_window.set_anchor(edge, true);
+        }
+        gtk_window.set_exclusive_zone(-1);
+        gtk_window.set_keyboard_mode(KeyboardMode::None);
+        Ok(())
+    }
+}
@@ -0,0 +1,192 @@
+// Панель-оверлей и затемнение: окна создаются один раз и дальше только показываются и прячутся
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, Monitor, WebviewUrl, WebviewWindow, WebviewWindowBuilder};
+
+use crate::display::{self, OverlayTarget};
+use crate::error::AppError;
+
+pub const OVERLAY_WINDOW: &str = "overlay";
+pub const VISIBILITY_EVENT: &str = "overlay://visibility";
+const DIM_WINDOW_PREFIX: &str = "overlay_dim_";
+
+pub struct Overlay {
+    panel: Option<WebviewWindow>,
+    dims: Vec<WebviewWindow>,
+    visible: bool,
+    target: OverlayTarget,
+    /// Логический размер панели из настроек
+    size: (f64, f64),
+}
+
+impl Default for Overlay {
+    fn default() -> Self {
+        Self { panel: None, dims: Vec::new(), visible: false, target: OverlayTarget::default(), size: (720.0, 120.0) }
+    }
+}
+
+/// Блокировку не брать на главном потоке: вызовы окон из других потоков ждут его
+pub type OverlayState = Mutex<Overlay>;
+
+impl Overlay {
+    /// Применяется при следующем показе панели
+    pub fn configure(&mut self, target: OverlayTarget, width: u32, height: u32) {
+        self.target = target;
+        self.size = (f64::from(width), f64::from(height));
+    }
+
+    /// Скрытое окно панели; создаётся при старте, чтобы показ не ждал загрузки webview
+    pub fn create(&mut self, app: &AppHandle) -> Result<&WebviewWindow, AppError> {
+        // Окно могли закрыть в обход состояния, тогда ссылка на него уже мёртвая
+        if self.panel.is_some() && app.get_webview_window(OVERLAY_WINDOW).is_none() {
+            self.panel = None;
+        }
+        if self.panel.is_none() {
+            let window = WebviewWindowBuilder::new(app, OVERLAY_WINDOW, WebviewUrl::App("overlay".into()))
+                .title("Overlay")
+                .inner_size(self.size.0, self.size.1)
+                .resizable(false)
+                .decorations(false)
+                .always_on_top(true)
+                .skip_taskbar(true)
+                .transparent(true)
+                .visible(false)
+                .build()?;
+            // macOS: уровень панели выше затемнения, показ во всех пространствах и поверх fullscreen
+            #[cfg(target_os = "macos")]
+            raise(app, &window, 101, true);
+            self.panel = Some(window);
+        }
+        Ok(self.panel.as_ref().unwrap())
+    }
+
+    pub fn show(&mut self, app: &AppHandle) -> Result<(), AppError> {
+        if self.visible {
+            return Ok(());
+        }
+
+        // Панель по центру выбранного монитора (под курсором или закреплённого)
+        let monitor = display::resolve(app, &self.target)?;
+        let (position, size) = display::centered(&monitor, self.size.0, self.size.1);
+        let panel = self.create(app)?;
+        // Сначала переносим окно на нужный монитор, потом задаём размер в его масштабе
+        panel.set_position(position)?;
+        panel.set_size(size)?;
+        // Анимацию появления проигрывает сама страница по этому событию
+        let _ = panel.emit_to(OVERLAY_WINDOW, VISIBILITY_EVENT, true);
+        panel.show()?;
+
+        // Не перехватываем фокус у активного приложения (если есть главное окно — вернём фокус)
+        if let Some(main_window) = app.get_webview_window("main") {
+            let _ = main_window.set_focus();
+        }
+
+        self.visible = true;
+        Ok(())
+    }
+
+    pub fn hide(&mut self) -> Result<(), AppError> {
+        if !self.visible {
+            return Ok(());
+        }
+        if let Some(panel) = &self.panel {
+            let _ = panel.emit_to(OVERLAY_WINDOW, VISIBILITY_EVENT, false);
+            panel.hide()?;
+        }
+        self.hide_dim();
+        self.visible = false;
+        Ok(())
+    }
+
+    pub fn toggle(&mut self, app: &AppHandle) -> Result<(), AppError> {
+        if self.visible {
+            self.hide()
+        } else {
+            self.show(app)
+        }
+    }
+
+    /// По окну затемнения на каждый монитор: одно окно на весь рабочий стол не переживает разный масштаб
+    pub fn show_dim(&mut self, app: &AppHandle) -> Result<(), AppError> {
+        let monitors = display::all(app)?;
+        // Мониторы могли отключить с прошлого показа
+        for dim in self.dims.drain(monitors.len().min(self.dims.len())..) {
+            let _ = dim.close();
+        }
+        for (index, monitor) in monitors.iter().enumerate() {
+            if index == self.dims.len() {
+                let dim = create_dim(app, index)?;
+                self.dims.push(dim);
+            }
+            let dim = &self.dims[index];
+            place_dim(dim, monitor)?;
+            dim.show()?;
+        }
+
+        // Панель остаётся поверх затемнения
+        if let Some(panel) = &self.panel {
+            if self.visible {
+                let _ = panel.set_focus();
+            }
+        }
+        Ok(())
+    }
+
+    pub fn hide_dim(&mut self) {
+        for dim in &self.dims {
+            let _ = dim.hide();
+        }
+    }
+}
+
+fn create_dim(app: &AppHandle, index: usize) -> Result<WebviewWindow, AppError> {
+    let label = format!("{}{}", DIM_WINDOW_PREFIX, index);
+    // Окно с таким label могло остаться, если состояние пересоздавалось
+    if let Some(window) = app.get_webview_window(&label) {
+        return Ok(window);
+    }
+    let dim = WebviewWindowBuilder::new(app, label, WebviewUrl::App("overlay/background".into()))
+        .title("Dim")
+        .resizable(false)
+        .decorations(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .transparent(true)
+        .focused(false)
+        .visible(false)
+        .build()?;
+    // macOS: ниже панели, во всех пространствах и кликабельно насквозь
+    #[cfg(target_os = "macos")]
+    {
+        dim.set_ignore_cursor_events(true)?;
+        raise(app, &dim, 100, false);
+    }
+    Ok(dim)
+}
+
+fn place_dim(dim: &WebviewWindow, monitor: &Monitor) -> Result<(), AppError> {
+    dim.set_position(*monitor.position())?;
+    dim.set_size(*monitor.size())?;
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn raise(app: &AppHandle, window: &WebviewWindow, level: i32, shadow: bool) {
+    use cocoa::appkit::NSWindowCollectionBehavior;
+    use cocoa::base::{id, NO};
+    use objc::runtime::YES;
+    use objc::{msg_send, sel, sel_impl};
+
+    let window = window.clone();
+    let _ = app.run_on_main_thread(move || unsafe {
+        if let Ok(ns_win_ptr) = window.ns_window() {
+            let ns_win: id = ns_win_ptr as id;
+            let _: () = msg_send![ns_win, setLevel: level];
+            let behavior = NSWindowCollectionBehavior::NSWindowCollectionBehaviorCanJoinAllSpaces
+                | NSWindowCollectionBehavior::NSWindowCollectionBehaviorFullScreenAuxiliary
+                | NSWindowCollectionBehavior::NSWindowCollectionBehaviorTransient;
+            let _: () = msg_send![ns_win, setCollectionBehavior: behavior];
+            let _: () = msg_send![ns_win, setOpaque: NO];
+            let _: () = msg_send![ns_win, setHasShadow: if shadow { YES } else { NO }];
+        }
+    });
+}
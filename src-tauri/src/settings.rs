@@ -0,0 +1,463 @@
+// Настройки приложения: типизированный JSON с версией в каталоге конфигурации
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::display::OverlayTarget;
+use crate::error::AppError;
+use crate::shortcuts::{self, ShortcutAction};
+use crate::skygen_client::DEFAULT_BACKEND_URL;
+use crate::updater::UpdateChannel;
+
+pub const CHANGED_EVENT: &str = "settings://changed";
+const SETTINGS_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    pub fn filter(self) -> log::LevelFilter {
+        match self {
+            LogLevel::Error => log::LevelFilter::Error,
+            LogLevel::Warn => log::LevelFilter::Warn,
+            LogLevel::Info => log::LevelFilter::Info,
+            LogLevel::Debug => log::LevelFilter::Debug,
+            LogLevel::Trace => log::LevelFilter::Trace,
+        }
+    }
+}
+
+/// Окружение бекенда; у каждого свои адреса и своё зарегистрированное устройство
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProfileId {
+    #[default]
+    Local,
+    Staging,
+    Production,
+    Custom,
+}
+
+impl ProfileId {
+    pub const ALL: [ProfileId; 4] = [ProfileId::Local, ProfileId::Staging, ProfileId::Production, ProfileId::Custom];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ProfileId::Local => "local",
+            ProfileId::Staging => "staging",
+            ProfileId::Production => "production",
+            ProfileId::Custom => "custom",
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            ProfileId::Local => "Local",
+            ProfileId::Staging => "Staging",
+            ProfileId::Production => "Production",
+            ProfileId::Custom => "Custom",
+        }
+    }
+
+    // Адреса стендов задаются при сборке; пустой адрес — профиль не настроен
+    fn default_url(self) -> &'static str {
+        match self {
+            ProfileId::Local => DEFAULT_BACKEND_URL,
+            ProfileId::Staging => option_env!("SKYGEN_STAGING_URL").unwrap_or_default(),
+            ProfileId::Production => option_env!("SKYGEN_PRODUCTION_URL").unwrap_or_default(),
+            ProfileId::Custom => "",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    pub url: String,
+    /// Адрес сокета вместо выданного при регистрации устройства (например, мок за прокси)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wss_url: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackendSettings {
+    pub profile: ProfileId,
+    pub profiles: BTreeMap<ProfileId, Profile>,
+}
+
+impl Default for BackendSettings {
+    fn default() -> Self {
+        let profiles = ProfileId::ALL
+            .iter()
+            .map(|&id| (id, Profile { url: id.default_url().into(), wss_url: None }))
+            .collect();
+        Self { profile: ProfileId::default(), profiles }
+    }
+}
+
+impl BackendSettings {
+    /// Профиль без записи в файле считается ненастроенным
+    pub fn active(&self) -> Profile {
+        self.profiles
+            .get(&self.profile)
+            .cloned()
+            .unwrap_or_else(|| Profile { url: String::new(), wss_url: None })
+    }
+}
+
+/// Стиль рамки по умолчанию: им рамка показывается по хоткею и из start_outline без width/blur
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutlineSettings {
+    pub color: String,
+    pub width: u32,
+    pub blur: u32,
+}
+
+impl Default for OutlineSettings {
+    fn default() -> Self {
+        Self { color: "#FF4D4F".into(), width: 4, blur: 0 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OverlaySettings {
+    pub width: u32,
+    pub height: u32,
+    pub target: OverlayTarget,
+}
+
+impl Default for OverlaySettings {
+    fn default() -> Self {
+        Self { width: 720, height: 120, target: OverlayTarget::default() }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UpdateSettings {
+    pub channel: UpdateChannel,
+    pub auto_check: bool,
+    /// Корень фида: манифест канала лежит в <feed_url>/<channel>/latest.json; локальный http-сервер годится для проверки
+    pub feed_url: String,
+}
+
+impl Default for UpdateSettings {
+    fn default() -> Self {
+        Self {
+            channel: UpdateChannel::default(),
+            auto_check: true,
+            feed_url: option_env!("SKYGEN_UPDATE_FEED").unwrap_or_default().into(),
+        }
+    }
+}
+
+impl UpdateSettings {
+    pub fn endpoint(&self) -> Option<String> {
+        if self.feed_url.is_empty() {
+            return None;
+        }
+        Some(format!("{}/{}/latest.json", self.feed_url.trim_end_matches('/'), self.channel.name()))
+    }
+}
+
+/// Сочетание для каждого назначенного действия; действия без записи не назначены
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ShortcutSettings(pub BTreeMap<ShortcutAction, String>);
+
+impl Default for ShortcutSettings {
+    fn default() -> Self {
+        Self(
+            ShortcutAction::ALL
+                .iter()
+                .filter_map(|&action| action.default_accelerator().map(|accelerator| (action, accelerator.to_string())))
+                .collect(),
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub backend: BackendSettings,
+    pub outline: OutlineSettings,
+    pub overlay: OverlaySettings,
+    pub log_level: LogLevel,
+    /// Запуск при входе в систему в фоновом режиме
+    pub autostart: bool,
+    pub updates: UpdateSettings,
+    pub shortcuts: ShortcutSettings,
+}
+
+#[derive(Serialize)]
+struct SettingsFile<'a> {
+    version: u32,
+    #[serde(flatten)]
+    settings: &'a Settings,
+}
+
+impl Settings {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.backend.active().url.is_empty() {
+            return Err(AppError::InvalidSettings(format!("backend: profile {} has no url", self.backend.profile.name())));
+        }
+        for (id, profile) in &self.backend.profiles {
+            let field = format!("backend.profiles.{}", id.name());
+            if !profile.url.is_empty() {
+                check_url(&format!("{}.url", field), &profile.url, &["http", "https"])?;
+            }
+            if let Some(wss_url) = &profile.wss_url {
+                check_url(&format!("{}.wss_url", field), wss_url, &["ws", "wss"])?;
+            }
+        }
+        let hex = self.outline.color.strip_prefix('#').unwrap_or_default();
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(AppError::InvalidSettings("outline.color: expected #RRGGBB".into()));
+        }
+        if !self.updates.feed_url.is_empty() {
+            check_url("updates.feed_url", &self.updates.feed_url, &["http", "https"])?;
+        }
+        check_range("outline.width", self.outline.width, 1, 64)?;
+        check_range("outline.blur", self.outline.blur, 0, 64)?;
+        check_range("overlay.width", self.overlay.width, 320, 2000)?;
+        check_range("overlay.height", self.overlay.height, 60, 1000)?;
+
+        let mut seen = HashMap::new();
+        for (action, accelerator) in &self.shortcuts.0 {
+            let field = format!("shortcuts.{}", action.name());
+            let shortcut = shortcuts::parse(accelerator).map_err(|e| AppError::InvalidSettings(format!("{}: {}", field, e)))?;
+            if let Some(other) = seen.insert(shortcut.id(), action) {
+                return Err(AppError::InvalidSettings(format!("{}: already used by {}", field, other.name())));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn check_url(field: &str, value: &str, schemes: &[&str]) -> Result<(), AppError> {
+    let url = reqwest::Url::parse(value).map_err(|e| AppError::InvalidSettings(format!("{}: {}", field, e)))?;
+    if !schemes.contains(&url.scheme()) {
+        return Err(AppError::InvalidSettings(format!("{}: expected {}", field, schemes.join(" or "))));
+    }
+    Ok(())
+}
+
+fn check_range(field: &str, value: u32, min: u32, max: u32) -> Result<(), AppError> {
+    if (min..=max).contains(&value) {
+        Ok(())
+    } else {
+        Err(AppError::InvalidSettings(format!("{}: expected {}..={}, got {}", field, min, max, value)))
+    }
+}
+
+/// Приводит сырой JSON к текущей схеме; шаги миграции добавляются сюда вместе с новой версией
+fn migrate(raw: Value) -> Result<Value, String> {
+    let version = match raw.get("version") {
+        Some(version) => version.as_u64().ok_or("version is not a number")?,
+        // Файл без версии — написанный руками по текущей схеме
+        None => u64::from(SETTINGS_VERSION),
+    };
+    if version == 0 {
+        return Err("unsupported settings version 0".into());
+    }
+    // Настройки от более новой сборки: известные поля читаем, остальные теряются при сохранении
+    if version > u64::from(SETTINGS_VERSION) {
+        log::warn!("settings version {} is newer than supported {}", version, SETTINGS_VERSION);
+    }
+    Ok(raw)
+}
+
+pub struct SettingsStore {
+    path: PathBuf,
+    current: Mutex<Settings>,
+}
+
+impl SettingsStore {
+    /// Нечитаемый или невалидный файл не мешает запуску: берутся значения по умолчанию
+    pub fn load(path: PathBuf) -> Self {
+        let settings = match std::fs::read(&path) {
+            Ok(bytes) => match serde_json::from_slice(&bytes)
+                .map_err(|e| e.to_string())
+                .and_then(migrate)
+                .and_then(|raw| serde_json::from_value::<Settings>(raw).map_err(|e| e.to_string()))
+            {
+                Ok(settings) => match settings.validate() {
+                    Ok(()) => settings,
+                    Err(e) => {
+                        log::warn!("ignoring invalid settings in {}: {}", path.display(), e);
+                        Settings::default()
+                    }
+                },
+                Err(e) => {
+                    log::warn!("ignoring unreadable settings file {}: {}", path.display(), e);
+                    Settings::default()
+                }
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Settings::default(),
+            Err(e) => {
+                log::warn!("failed to read settings file {}: {}", path.display(), e);
+                Settings::default()
+            }
+        };
+        Self { path, current: Mutex::new(settings) }
+    }
+
+    pub fn get(&self) -> Settings {
+        self.current.lock().unwrap().clone()
+    }
+
+    /// Проверяет и сохраняет настройки целиком; при ошибке прежние остаются в силе
+    pub fn update(&self, settings: Settings) -> Result<Settings, AppError> {
+        settings.validate()?;
+        let mut current = self.current.lock().unwrap();
+        self.save(&settings).map_err(|e| AppError::Internal(e.to_string()))?;
+        *current = settings.clone();
+        Ok(settings)
+    }
+
+    fn save(&self, settings: &Settings) -> std::io::Result<()> {
+        let file = SettingsFile { version: SETTINGS_VERSION, settings };
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let tmp = self.path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(&file)?)?;
+        std::fs::rename(&tmp, &self.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn load(raw: Value) -> Result<Settings, String> {
+        migrate(raw).and_then(|raw| serde_json::from_value(raw).map_err(|e| e.to_string()))
+    }
+
+    fn invalid_field(settings: &Settings) -> String {
+        match settings.validate() {
+            Err(AppError::InvalidSettings(e)) => e.split(':').next().unwrap().to_string(),
+            other => panic!("expected invalid settings, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unsupported_versions_are_rejected() {
+        assert!(load(json!({ "version": 0 })).is_err());
+        assert!(load(json!({ "version": "2" })).is_err());
+    }
+
+    #[test]
+    fn file_without_version_uses_current_schema() {
+        let settings = load(json!({ "backend": { "profile": "staging", "profiles": { "staging": { "url": "https://staging.example.com" } } } })).unwrap();
+        assert_eq!(settings.backend.profile, ProfileId::Staging);
+        assert_eq!(settings.backend.active().url, "https://staging.example.com");
+    }
+
+    #[test]
+    fn saved_settings_load_back() {
+        let path = std::env::temp_dir().join(format!("skygen-settings-{}", uuid::Uuid::new_v4())).join("settings.json");
+        let store = SettingsStore::load(path.clone());
+        let mut settings = store.get();
+        settings.outline.width = 12;
+        settings.updates.feed_url = "http://127.0.0.1:8000".into();
+        store.update(settings.clone()).unwrap();
+
+        let raw: Value = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(raw["version"], SETTINGS_VERSION);
+        assert_eq!(SettingsStore::load(path.clone()).get(), settings);
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn defaults_are_valid() {
+        assert!(Settings::default().validate().is_ok());
+    }
+
+    #[test]
+    fn ranges_are_checked_inclusively() {
+        let mut settings = Settings::default();
+        settings.outline.width = 64;
+        settings.outline.blur = 0;
+        settings.overlay.width = 320;
+        settings.overlay.height = 1000;
+        assert!(settings.validate().is_ok());
+
+        settings.outline.width = 0;
+        assert_eq!(invalid_field(&settings), "outline.width");
+        settings.outline.width = 4;
+        settings.outline.blur = 65;
+        assert_eq!(invalid_field(&settings), "outline.blur");
+        settings.outline.blur = 0;
+        settings.overlay.width = 2001;
+        assert_eq!(invalid_field(&settings), "overlay.width");
+        settings.overlay.width = 720;
+        settings.overlay.height = 59;
+        assert_eq!(invalid_field(&settings), "overlay.height");
+    }
+
+    #[test]
+    fn outline_color_must_be_hex() {
+        let mut settings = Settings::default();
+        for color in ["FF4D4F", "#FF4D4", "#GG4D4F"] {
+            settings.outline.color = color.into();
+            assert_eq!(invalid_field(&settings), "outline.color");
+        }
+    }
+
+    #[test]
+    fn url_schemes_are_checked() {
+        let mut settings = Settings::default();
+        settings.backend.profiles.insert(ProfileId::Staging, Profile { url: "ws://staging.example.com".into(), wss_url: None });
+        assert_eq!(invalid_field(&settings), "backend.profiles.staging.url");
+
+        settings.backend.profiles.insert(
+            ProfileId::Staging,
+            Profile { url: "https://staging.example.com".into(), wss_url: Some("https://staging.example.com/ws".into()) },
+        );
+        assert_eq!(invalid_field(&settings), "backend.profiles.staging.wss_url");
+
+        settings.backend.profiles.insert(
+            ProfileId::Staging,
+            Profile { url: "https://staging.example.com".into(), wss_url: Some("wss://staging.example.com/ws".into()) },
+        );
+        settings.updates.feed_url = "file:///tmp/feed".into();
+        assert_eq!(invalid_field(&settings), "updates.feed_url");
+
+        settings.updates.feed_url = "not a url".into();
+        assert_eq!(invalid_field(&settings), "updates.feed_url");
+
+        settings.updates.feed_url = "https://updates.example.com".into();
+        assert!(settings.validate().is_ok());
+    }
+
+    #[test]
+    fn active_profile_needs_url() {
+        let mut settings = Settings::default();
+        settings.backend.profile = ProfileId::Custom;
+        assert_eq!(invalid_field(&settings), "backend");
+    }
+
+    #[test]
+    fn duplicate_or_invalid_shortcuts_are_rejected() {
+        let mut settings = Settings::default();
+        settings.shortcuts.0.insert(ShortcutAction::ToggleOutline, "Alt+Space".into());
+        assert!(matches!(settings.validate(), Err(AppError::InvalidSettings(e)) if e.contains("already used")));
+
+        settings.shortcuts.0.insert(ShortcutAction::ToggleOutline, "Alt+".into());
+        assert!(matches!(settings.validate(), Err(AppError::InvalidSettings(e)) if e.starts_with("shortcuts.toggle_outline")));
+    }
+}
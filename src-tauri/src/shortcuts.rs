@@ -0,0 +1,230 @@
+// Глобальные хоткеи: действие приложения -> сочетание клавиш, хранится в настройках
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+use crate::error::AppError;
+use crate::outline::OutlineState;
+use crate::overlay::OverlayState;
+use crate::settings::{self, SettingsStore, ShortcutSettings};
+
+pub const TRIGGER_EVENT: &str = "shortcut://triggered";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShortcutAction {
+    ToggleOverlay,
+    CancelTask,
+    PushToTalk,
+    ToggleOutline,
+}
+
+impl ShortcutAction {
+    pub const ALL: [ShortcutAction; 4] = [
+        ShortcutAction::ToggleOverlay,
+        ShortcutAction::CancelTask,
+        ShortcutAction::PushToTalk,
+        ShortcutAction::ToggleOutline,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ShortcutAction::ToggleOverlay => "toggle_overlay",
+            ShortcutAction::CancelTask => "cancel_task",
+            ShortcutAction::PushToTalk => "push_to_talk",
+            ShortcutAction::ToggleOutline => "toggle_outline",
+        }
+    }
+
+    /// Сочетание при первом запуске; Option+Space на macOS это Alt+Space
+    pub fn default_accelerator(self) -> Option<&'static str> {
+        match self {
+            ShortcutAction::ToggleOverlay => Some("Alt+Space"),
+            ShortcutAction::CancelTask => Some("CommandOrControl+Alt+Period"),
+            _ => None,
+        }
+    }
+}
+
+/// Состояние для UI: registered = false, если ОС не дала зарегистрировать сочетание
+#[derive(Debug, Clone, Serialize)]
+pub struct ShortcutInfo {
+    pub action: ShortcutAction,
+    pub accelerator: Option<String>,
+    pub registered: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+struct Entry {
+    accelerator: String,
+    shortcut: Option<Shortcut>,
+    error: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+struct Trigger {
+    action: ShortcutAction,
+    pressed: bool,
+}
+
+pub struct Shortcuts {
+    entries: Mutex<HashMap<ShortcutAction, Entry>>,
+}
+
+impl Shortcuts {
+    /// Сочетания из настроек; регистрируются отдельно, через register_all
+    pub fn new(bindings: &ShortcutSettings) -> Self {
+        Self { entries: Mutex::new(entries_from(bindings)) }
+    }
+
+    /// Регистрирует сохранённые сочетания; отказ ОС не фатален и виден в list
+    pub fn register_all(&self, app: &AppHandle) {
+        let mut entries = self.entries.lock().unwrap();
+        for (&action, entry) in entries.iter_mut() {
+            match parse(&entry.accelerator).and_then(|shortcut| register(app, action, shortcut)) {
+                Ok(shortcut) => entry.shortcut = Some(shortcut),
+                Err(e) => {
+                    log::warn!("shortcut {} for {:?} is unavailable: {}", entry.accelerator, action, e);
+                    entry.error = Some(e.to_string());
+                }
+            }
+        }
+    }
+
+    /// Заменяет все сочетания сохранёнными в настройках, когда они изменились через update_settings
+    pub fn apply(&self, app: &AppHandle, bindings: &ShortcutSettings) {
+        {
+            let mut entries = self.entries.lock().unwrap();
+            for shortcut in entries.values().filter_map(|entry| entry.shortcut) {
+                let _ = app.global_shortcut().unregister(shortcut);
+            }
+            *entries = entries_from(bindings);
+        }
+        self.register_all(app);
+    }
+
+    pub fn list(&self) -> Vec<ShortcutInfo> {
+        let entries = self.entries.lock().unwrap();
+        ShortcutAction::ALL
+            .iter()
+            .map(|&action| match entries.get(&action) {
+                Some(entry) => ShortcutInfo {
+                    action,
+                    accelerator: Some(entry.accelerator.clone()),
+                    registered: entry.shortcut.is_some(),
+                    error: entry.error.clone(),
+                },
+                None => ShortcutInfo { action, accelerator: None, registered: false, error: None },
+            })
+            .collect()
+    }
+
+    /// Назначает сочетание действию, заменяя прежнее; при отказе ОС прежнее сочетание остаётся
+    pub fn bind(&self, app: &AppHandle, action: ShortcutAction, accelerator: &str) -> Result<(), AppError> {
+        let shortcut = parse(accelerator)?;
+        let mut entries = self.entries.lock().unwrap();
+        if let Some((&other, _)) = entries
+            .iter()
+            .find(|(&other, entry)| other != action && entry.shortcut.map(|s| s.id()) == Some(shortcut.id()))
+        {
+            return Err(AppError::ShortcutConflict(other.name().into()));
+        }
+
+        let previous = entries.get(&action).and_then(|entry| entry.shortcut);
+        if previous.map(|s| s.id()) != Some(shortcut.id()) {
+            if let Some(previous) = previous {
+                let _ = app.global_shortcut().unregister(previous);
+            }
+            if let Err(e) = register(app, action, shortcut) {
+                if let Some(previous) = previous {
+                    let _ = register(app, action, previous);
+                }
+                return Err(e);
+            }
+        }
+        entries.insert(action, Entry { accelerator: accelerator.into(), shortcut: Some(shortcut), error: None });
+        drop(entries);
+        self.save(app)
+    }
+
+    pub fn rebind(&self, app: &AppHandle, action: ShortcutAction, accelerator: &str) -> Result<(), AppError> {
+        if !self.entries.lock().unwrap().contains_key(&action) {
+            return Err(AppError::ShortcutNotBound);
+        }
+        self.bind(app, action, accelerator)
+    }
+
+    pub fn unbind(&self, app: &AppHandle, action: ShortcutAction) -> Result<(), AppError> {
+        let removed = self.entries.lock().unwrap().remove(&action).ok_or(AppError::ShortcutNotBound)?;
+        if let Some(shortcut) = removed.shortcut {
+            app.global_shortcut().unregister(shortcut).map_err(|e| AppError::ShortcutUnavailable(e.to_string()))?;
+        }
+        self.save(app)
+    }
+
+    /// Сочетания сохраняются в общих настройках; открытые окна узнают о них из settings://changed
+    fn save(&self, app: &AppHandle) -> Result<(), AppError> {
+        let bindings = ShortcutSettings(
+            self.entries.lock().unwrap().iter().map(|(&action, entry)| (action, entry.accelerator.clone())).collect(),
+        );
+        let store = app.state::<SettingsStore>();
+        let mut updated = store.get();
+        updated.shortcuts = bindings;
+        let updated = store.update(updated)?;
+        let _ = app.emit(settings::CHANGED_EVENT, &updated);
+        Ok(())
+    }
+}
+
+fn entries_from(bindings: &ShortcutSettings) -> HashMap<ShortcutAction, Entry> {
+    bindings
+        .0
+        .iter()
+        .map(|(&action, accelerator)| (action, Entry { accelerator: accelerator.clone(), shortcut: None, error: None }))
+        .collect()
+}
+
+pub fn parse(accelerator: &str) -> Result<Shortcut, AppError> {
+    Shortcut::from_str(accelerator).map_err(|e| AppError::InvalidShortcut(e.to_string()))
+}
+
+fn register(app: &AppHandle, action: ShortcutAction, shortcut: Shortcut) -> Result<Shortcut, AppError> {
+    app.global_shortcut()
+        .on_shortcut(shortcut, move |app, _shortcut, event| trigger(app, action, event.state))
+        .map_err(|e| AppError::ShortcutUnavailable(e.to_string()))?;
+    Ok(shortcut)
+}
+
+/// Обработчик идёт на главном потоке, поэтому состояние окон меняем из пула
+fn trigger(app: &AppHandle, action: ShortcutAction, state: ShortcutState) {
+    let pressed = state == ShortcutState::Pressed;
+    // Push-to-talk и прочее, что живёт в UI, слушают событие; отпускание нужно только им
+    let _ = app.emit(TRIGGER_EVENT, Trigger { action, pressed });
+    if !pressed {
+        return;
+    }
+
+    let app = app.clone();
+    match action {
+        ShortcutAction::ToggleOverlay => {
+            tauri::async_runtime::spawn_blocking(move || {
+                if let Err(e) = app.state::<OverlayState>().lock().unwrap().toggle(&app) {
+                    log::warn!("failed to toggle overlay: {}", e);
+                }
+            });
+        }
+        ShortcutAction::ToggleOutline => {
+            tauri::async_runtime::spawn_blocking(move || {
+                if let Err(e) = app.state::<OutlineState>().lock().unwrap().toggle(&app) {
+                    log::warn!("failed to toggle outline: {}", e);
+                }
+            });
+        }
+        ShortcutAction::CancelTask => crate::emergency_stop(&app),
+        ShortcutAction::PushToTalk => {}
+    }
+}
@@ -0,0 +1,560 @@
+// Нативный клиент Skygen бекенда (замена SkygenClient из python/main.py)
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::credentials::{CredentialStore, StoredCredentials};
+
+pub const DEFAULT_BACKEND_URL: &str = "http://localhost:8000";
+
+fn env_backend_url() -> Option<String> {
+    std::env::var("SKYGEN_BACKEND_URL").ok()
+}
+
+// Обновляем access token заранее, если до истечения осталось меньше этого запаса
+const REFRESH_LEEWAY_SECS: u64 = 30;
+
+/// Клиент в managed state Tauri; tokio Mutex, потому что запросы держат его через await
+pub type SkygenState = tokio::sync::Mutex<SkygenClient>;
+
+pub type SessionExpiredHook = Arc<dyn Fn() + Send + Sync>;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoginData {
+    pub access_token: String,
+    pub refresh_token: String,
+    #[serde(default)]
+    pub token_type: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RefreshData {
+    access_token: String,
+    #[serde(default)]
+    refresh_token: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceCredentials {
+    #[serde(deserialize_with = "string_or_number")]
+    pub device_id: String,
+    pub device_token: String,
+    pub wss_url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kid: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum ClientError {
+    Network(String),
+    InvalidCredentials,
+    RateLimited,
+    AccountLocked,
+    EmailInUse,
+    InvalidMagicLink,
+    NotAuthenticated,
+    SessionExpired,
+    Backend { status: u16, body: String },
+    InvalidResponse(String),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Network(e) => write!(f, "Network error: {}", e),
+            ClientError::InvalidCredentials => write!(f, "Invalid credentials"),
+            ClientError::RateLimited => write!(f, "Too many login attempts"),
+            ClientError::AccountLocked => write!(f, "Account temporarily locked"),
+            ClientError::EmailInUse => write!(f, "Email already in use"),
+            ClientError::InvalidMagicLink => write!(f, "Magic link is invalid or expired"),
+            ClientError::NotAuthenticated => write!(f, "Not authenticated"),
+            ClientError::SessionExpired => write!(f, "Session expired, please sign in again"),
+            ClientError::Backend { status, body } => write!(f, "Backend error ({}): {}", status, body),
+            ClientError::InvalidResponse(e) => write!(f, "Failed to parse response: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+impl From<reqwest::Error> for ClientError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_decode() {
+            ClientError::InvalidResponse(e.to_string())
+        } else {
+            ClientError::Network(e.to_string())
+        }
+    }
+}
+
+pub struct SkygenClient {
+    http: reqwest::Client,
+    backend_url: String,
+    wss_url: Option<String>,
+    access_token: Option<String>,
+    refresh_token: Option<String>,
+    device: Option<DeviceCredentials>,
+    store: Option<Arc<dyn CredentialStore>>,
+    on_session_expired: Option<SessionExpiredHook>,
+}
+
+impl SkygenClient {
+    pub fn new(backend_url: impl Into<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            backend_url: backend_url.into().trim_end_matches('/').to_string(),
+            wss_url: None,
+            access_token: None,
+            refresh_token: None,
+            device: None,
+            store: None,
+            on_session_expired: None,
+        }
+    }
+
+    /// Адреса из профиля настроек; SKYGEN_BACKEND_URL перекрывает REST-адрес (например, для локального мока)
+    pub fn from_profile(backend_url: &str, wss_url: Option<String>) -> Self {
+        let mut client = Self::new(env_backend_url().unwrap_or_else(|| backend_url.to_string()));
+        client.wss_url = wss_url;
+        client
+    }
+
+    /// Подключает хранилище и восстанавливает сессию, сохранённую при прошлом запуске
+    pub fn with_store(mut self, store: Arc<dyn CredentialStore>) -> Self {
+        self.restore(store);
+        self
+    }
+
+    /// Переключение профиля без перезапуска: сессия и устройство берутся из хранилища нового профиля
+    pub fn switch_profile(&mut self, backend_url: &str, wss_url: Option<String>, store: Arc<dyn CredentialStore>) {
+        match env_backend_url() {
+            Some(_) => log::info!("backend url is pinned by SKYGEN_BACKEND_URL, ignoring profile url"),
+            None => self.backend_url = backend_url.trim_end_matches('/').to_string(),
+        }
+        self.wss_url = wss_url;
+        self.access_token = None;
+        self.refresh_token = None;
+        self.device = None;
+        self.restore(store);
+    }
+
+    fn restore(&mut self, store: Arc<dyn CredentialStore>) {
+        match store.load() {
+            Ok(Some(saved)) => {
+                self.access_token = saved.access_token;
+                self.refresh_token = saved.refresh_token;
+                self.device = saved.device;
+            }
+            Ok(None) => {}
+            Err(e) => log::warn!("failed to load credentials from {}: {}", store.name(), e),
+        }
+        self.store = Some(store);
+    }
+
+    /// Вызывается, когда refresh token больше не принимается и нужен повторный вход
+    pub fn on_session_expired(mut self, hook: SessionExpiredHook) -> Self {
+        self.on_session_expired = Some(hook);
+        self
+    }
+
+    fn persist(&self) {
+        let Some(store) = &self.store else { return };
+        let credentials = StoredCredentials {
+            access_token: self.access_token.clone(),
+            refresh_token: self.refresh_token.clone(),
+            device: self.device.clone(),
+        };
+        if let Err(e) = store.save(&credentials) {
+            log::error!("failed to save credentials to {}: {}", store.name(), e);
+        }
+    }
+
+    /// Забывает сессию и устройство, в том числе в хранилище
+    pub fn logout(&mut self) {
+        self.access_token = None;
+        self.refresh_token = None;
+        self.device = None;
+        if let Some(store) = &self.store {
+            if let Err(e) = store.clear() {
+                log::error!("failed to clear credentials in {}: {}", store.name(), e);
+            }
+        }
+    }
+
+    pub fn is_authenticated(&self) -> bool {
+        self.access_token.is_some()
+    }
+
+    pub fn device(&self) -> Option<&DeviceCredentials> {
+        self.device.as_ref()
+    }
+
+    /// Учётные данные для сокета: адрес из профиля, если он задан, иначе выданный при регистрации
+    pub fn socket_device(&self) -> Option<DeviceCredentials> {
+        let mut device = self.device.clone()?;
+        if let Some(wss_url) = &self.wss_url {
+            device.wss_url = wss_url.clone();
+        }
+        Some(device)
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.backend_url, path)
+    }
+
+    pub async fn signup(&self, email: &str, password: &str) -> Result<serde_json::Value, ClientError> {
+        let response = self
+            .http
+            .post(self.url("/v1/auth/signup"))
+            .json(&serde_json::json!({ "email": email, "password": password }))
+            .send()
+            .await?;
+
+        match response.status().as_u16() {
+            201 => Ok(response.json().await?),
+            409 => Err(ClientError::EmailInUse),
+            status => Err(backend_error(status, response).await),
+        }
+    }
+
+    pub async fn login(&mut self, email: &str, password: &str) -> Result<LoginData, ClientError> {
+        let response = self
+            .http
+            .post(self.url("/v1/auth/login"))
+            .json(&serde_json::json!({ "email": email, "password": password }))
+            .send()
+            .await?;
+
+        match response.status().as_u16() {
+            200 => {
+                let data: LoginData = response.json().await?;
+                self.start_session(&data);
+                Ok(data)
+            }
+            401 => Err(ClientError::InvalidCredentials),
+            429 => Err(ClientError::RateLimited),
+            423 => Err(ClientError::AccountLocked),
+            status => Err(backend_error(status, response).await),
+        }
+    }
+
+    /// Вход по одноразовой ссылке из письма (skygen://auth/verify?token=…) без браузера
+    pub async fn verify_magic_link(&mut self, token: &str) -> Result<LoginData, ClientError> {
+        let response = self
+            .http
+            .post(self.url("/v1/auth/magic-link/verify"))
+            .json(&serde_json::json!({ "token": token }))
+            .send()
+            .await?;
+
+        match response.status().as_u16() {
+            200 => {
+                let data: LoginData = response.json().await?;
+                self.start_session(&data);
+                Ok(data)
+            }
+            // Ссылка одноразовая: повторный переход по ней тоже сюда
+            400 | 401 | 404 | 410 => Err(ClientError::InvalidMagicLink),
+            429 => Err(ClientError::RateLimited),
+            423 => Err(ClientError::AccountLocked),
+            status => Err(backend_error(status, response).await),
+        }
+    }
+
+    fn start_session(&mut self, data: &LoginData) {
+        self.access_token = Some(data.access_token.clone());
+        self.refresh_token = Some(data.refresh_token.clone());
+        self.persist();
+    }
+
+    pub async fn enroll_device(&mut self, device_name: Option<String>) -> Result<DeviceCredentials, ClientError> {
+        let platform = platform_name();
+        let device_name = device_name
+            .unwrap_or_else(|| format!("{}-{}", gethostname::gethostname().to_string_lossy(), platform));
+
+        let payload = serde_json::json!({
+            "device_name": device_name,
+            "platform": platform,
+            "capabilities": {
+                "screenshot": true,
+                "accessibility": true,
+                "input": true,
+                "filesystem": true,
+                "platform": platform,
+                "python": false,
+            },
+            "idempotency_key": uuid::Uuid::new_v4().to_string(),
+        });
+
+        let url = self.url("/v1/devices/enroll");
+        let response = self
+            .send_authorized(|http| http.post(&url).json(&payload))
+            .await?;
+
+        match response.status().as_u16() {
+            201 => {
+                let device: DeviceCredentials = response.json().await?;
+                self.device = Some(device.clone());
+                self.persist();
+                Ok(device)
+            }
+            status => Err(backend_error(status, response).await),
+        }
+    }
+
+    /// Отправляет запрос с access token: заранее обновляет истекающий токен,
+    /// а на 401 обновляет его и повторяет запрос один раз
+    async fn send_authorized<F>(&mut self, build: F) -> Result<reqwest::Response, ClientError>
+    where
+        F: Fn(&reqwest::Client) -> reqwest::RequestBuilder,
+    {
+        if self.access_token.is_none() {
+            return Err(ClientError::NotAuthenticated);
+        }
+        if self.access_token.as_deref().map(token_expires_soon).unwrap_or(false) {
+            self.refresh().await?;
+        }
+
+        let token = self.access_token.clone().ok_or(ClientError::NotAuthenticated)?;
+        let response = build(&self.http).bearer_auth(token).send().await?;
+        if response.status() != reqwest::StatusCode::UNAUTHORIZED {
+            return Ok(response);
+        }
+
+        self.refresh().await?;
+        let token = self.access_token.clone().ok_or(ClientError::NotAuthenticated)?;
+        let response = build(&self.http).bearer_auth(token).send().await?;
+        if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+            return Err(self.expire_session());
+        }
+        Ok(response)
+    }
+
+    async fn refresh(&mut self) -> Result<(), ClientError> {
+        let Some(refresh_token) = self.refresh_token.clone() else {
+            return Err(self.expire_session());
+        };
+
+        let response = self
+            .http
+            .post(self.url("/v1/auth/refresh"))
+            .json(&serde_json::json!({ "refresh_token": refresh_token }))
+            .send()
+            .await?;
+
+        match response.status().as_u16() {
+            200 => {
+                let data: RefreshData = response.json().await?;
+                self.access_token = Some(data.access_token);
+                if let Some(rotated) = data.refresh_token {
+                    self.refresh_token = Some(rotated);
+                }
+                self.persist();
+                Ok(())
+            }
+            400 | 401 | 403 => Err(self.expire_session()),
+            status => Err(backend_error(status, response).await),
+        }
+    }
+
+    // Токены больше не действительны: забываем их (устройство остаётся) и сообщаем UI
+    fn expire_session(&mut self) -> ClientError {
+        self.access_token = None;
+        self.refresh_token = None;
+        self.persist();
+        if let Some(hook) = &self.on_session_expired {
+            hook();
+        }
+        ClientError::SessionExpired
+    }
+}
+
+/// true, если в JWT есть claim exp и он наступит в ближайшие REFRESH_LEEWAY_SECS
+fn token_expires_soon(token: &str) -> bool {
+    let Some(payload) = token.split('.').nth(1) else { return false };
+    let Ok(bytes) = URL_SAFE_NO_PAD.decode(payload.trim_end_matches('=')) else { return false };
+    let Ok(claims) = serde_json::from_slice::<serde_json::Value>(&bytes) else { return false };
+    let Some(exp) = claims.get("exp").and_then(|v| v.as_u64()) else { return false };
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    exp <= now + REFRESH_LEEWAY_SECS
+}
+
+async fn backend_error(status: u16, response: reqwest::Response) -> ClientError {
+    let body = response.text().await.unwrap_or_default();
+    ClientError::Backend { status, body }
+}
+
+/// Имя платформы в том же виде, что отдавал platform.system() в Python
+pub fn platform_name() -> &'static str {
+    match std::env::consts::OS {
+        "macos" => "Darwin",
+        "linux" => "Linux",
+        "windows" => "Windows",
+        other => other,
+    }
+}
+
+// Бекенд может вернуть идентификаторы как числа или как строки (UUID)
+pub(crate) fn string_or_number<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::String(s) => Ok(s),
+        serde_json::Value::Number(n) => Ok(n.to_string()),
+        other => Err(serde::de::Error::custom(format!("unexpected id: {}", other))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+
+    #[derive(Debug, Clone)]
+    struct Request {
+        path: String,
+        bearer: Option<String>,
+        body: serde_json::Value,
+    }
+
+    type Requests = Arc<Mutex<Vec<Request>>>;
+
+    /// Мок бекенда: одно соединение — один запрос, ответ выбирает `route`
+    async fn serve<F>(route: F) -> (String, Requests)
+    where
+        F: Fn(&Request) -> (u16, serde_json::Value) + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests: Requests = Arc::default();
+        let recorded = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let mut stream = BufReader::new(stream);
+                let mut line = String::new();
+                stream.read_line(&mut line).await.unwrap();
+                let path = line.split_whitespace().nth(1).unwrap_or_default().to_string();
+                let (mut length, mut bearer) = (0, None);
+                loop {
+                    line.clear();
+                    stream.read_line(&mut line).await.unwrap();
+                    let header = line.trim_end();
+                    if header.is_empty() {
+                        break;
+                    }
+                    let (name, value) = header.split_once(':').unwrap();
+                    match name.to_ascii_lowercase().as_str() {
+                        "content-length" => length = value.trim().parse().unwrap(),
+                        "authorization" => bearer = value.trim().strip_prefix("Bearer ").map(str::to_string),
+                        _ => {}
+                    }
+                }
+                let mut body = vec![0; length];
+                stream.read_exact(&mut body).await.unwrap();
+                let request = Request { path, bearer, body: serde_json::from_slice(&body).unwrap_or_default() };
+
+                let (status, reply) = route(&request);
+                recorded.lock().unwrap().push(request);
+                let reply = reply.to_string();
+                let response = format!(
+                    "HTTP/1.1 {} X\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    status,
+                    reply.len(),
+                    reply
+                );
+                stream.get_mut().write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        (url, requests)
+    }
+
+    fn session(access: &str) -> serde_json::Value {
+        serde_json::json!({ "access_token": access, "refresh_token": "refresh-1", "token_type": "bearer" })
+    }
+
+    fn device() -> serde_json::Value {
+        serde_json::json!({ "device_id": 7, "device_token": "dt", "wss_url": "ws://localhost/ws" })
+    }
+
+    fn paths(requests: &Requests) -> Vec<String> {
+        requests.lock().unwrap().iter().map(|r| r.path.clone()).collect()
+    }
+
+    #[tokio::test]
+    async fn login_starts_session() {
+        let (url, requests) = serve(|_| (200, session("access-1"))).await;
+        let mut client = SkygenClient::new(url);
+
+        let data = client.login("a@b.c", "secret").await.unwrap();
+
+        assert_eq!(data.access_token, "access-1");
+        assert!(client.is_authenticated());
+        let request = requests.lock().unwrap()[0].clone();
+        assert_eq!(request.path, "/v1/auth/login");
+        assert_eq!(request.body["email"], "a@b.c");
+    }
+
+    #[tokio::test]
+    async fn login_maps_error_statuses() {
+        for (status, expected) in [(401, "InvalidCredentials"), (429, "RateLimited"), (423, "AccountLocked")] {
+            let (url, _) = serve(move |_| (status, serde_json::json!({}))).await;
+            let mut client = SkygenClient::new(url);
+
+            let error = client.login("a@b.c", "secret").await.unwrap_err();
+
+            assert_eq!(format!("{:?}", error), expected);
+            assert!(!client.is_authenticated());
+        }
+    }
+
+    #[tokio::test]
+    async fn unauthorized_request_is_refreshed_and_retried() {
+        let (url, requests) = serve(|request| match request.path.as_str() {
+            "/v1/auth/login" => (200, session("stale")),
+            "/v1/auth/refresh" => (200, serde_json::json!({ "access_token": "fresh" })),
+            _ if request.bearer.as_deref() == Some("fresh") => (201, device()),
+            _ => (401, serde_json::json!({})),
+        })
+        .await;
+        let mut client = SkygenClient::new(url);
+        client.login("a@b.c", "secret").await.unwrap();
+
+        let device = client.enroll_device(Some("test".into())).await.unwrap();
+
+        assert_eq!(device.device_id, "7");
+        assert_eq!(
+            paths(&requests),
+            ["/v1/auth/login", "/v1/devices/enroll", "/v1/auth/refresh", "/v1/devices/enroll"]
+        );
+        assert_eq!(requests.lock().unwrap()[2].body["refresh_token"], "refresh-1");
+    }
+
+    #[tokio::test]
+    async fn failed_refresh_expires_session() {
+        let (url, requests) = serve(|request| match request.path.as_str() {
+            "/v1/auth/login" => (200, session("stale")),
+            _ => (401, serde_json::json!({})),
+        })
+        .await;
+        let expired = Arc::new(AtomicUsize::new(0));
+        let counter = expired.clone();
+        let mut client = SkygenClient::new(url).on_session_expired(Arc::new(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        }));
+        client.login("a@b.c", "secret").await.unwrap();
+
+        let error = client.enroll_device(None).await.unwrap_err();
+
+        assert!(matches!(error, ClientError::SessionExpired));
+        assert_eq!(expired.load(Ordering::SeqCst), 1);
+        assert!(!client.is_authenticated());
+        // Повтора после неудачного refresh нет
+        assert_eq!(paths(&requests), ["/v1/auth/login", "/v1/devices/enroll", "/v1/auth/refresh"]);
+    }
+}
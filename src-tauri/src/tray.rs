@@ -0,0 +1,228 @@
+// Иконка в трее: статус агента, текущая задача и быстрые действия, даже если окна приложения скрыты
+use tauri::image::Image;
+use tauri::menu::{CheckMenuItem, IsMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::{TrayIcon, TrayIconBuilder};
+use tauri::{AppHandle, Listener, Manager};
+
+use crate::agent::{Agent, ConnectionState, CONNECTION_EVENT, STATE_EVENT, TASK_EVENT};
+use crate::overlay::OverlayState;
+use crate::settings::{self, ProfileId, SettingsStore};
+use crate::skygen_client::SkygenState;
+
+const CONNECT_ITEM: &str = "connect";
+const TOGGLE_OVERLAY_ITEM: &str = "toggle_overlay";
+const EMERGENCY_STOP_ITEM: &str = "emergency_stop";
+const STOP_AGENT_ITEM: &str = "stop_agent";
+const SHOW_WINDOW_ITEM: &str = "show_window";
+const QUIT_ITEM: &str = "quit";
+const PROFILE_ITEM_PREFIX: &str = "profile:";
+
+/// То же, что StatusResponse, сведённое к одному состоянию для иконки и меню
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Status {
+    SignedOut,
+    NotEnrolled,
+    Disconnected,
+    Connecting,
+    Connected,
+}
+
+impl Status {
+    fn label(self) -> &'static str {
+        match self {
+            Status::SignedOut => "Signed out",
+            Status::NotEnrolled => "Device not enrolled",
+            Status::Disconnected => "Disconnected",
+            Status::Connecting => "Connecting…",
+            Status::Connected => "Connected",
+        }
+    }
+
+    /// Цвет точки поверх иконки приложения
+    fn badge(self) -> [u8; 3] {
+        match self {
+            Status::SignedOut | Status::NotEnrolled => [0x8C, 0x8C, 0x8C],
+            Status::Disconnected => [0xFF, 0x4D, 0x4F],
+            Status::Connecting => [0xFF, 0xB0, 0x20],
+            Status::Connected => [0x2E, 0xC4, 0x5A],
+        }
+    }
+}
+
+pub struct Tray {
+    icon: TrayIcon,
+    base_icon: Option<Image<'static>>,
+    status: MenuItem,
+    task: MenuItem,
+    connect: MenuItem,
+    stop_agent: MenuItem,
+    profiles: Vec<(ProfileId, CheckMenuItem)>,
+}
+
+pub fn create(app: &AppHandle) -> tauri::Result<()> {
+    let status = MenuItem::with_id(app, "status", Status::SignedOut.label(), false, None::<&str>)?;
+    let task = MenuItem::with_id(app, "task", "No task", false, None::<&str>)?;
+    let connect = MenuItem::with_id(app, CONNECT_ITEM, "Connect", false, None::<&str>)?;
+    let toggle_overlay = MenuItem::with_id(app, TOGGLE_OVERLAY_ITEM, "Toggle overlay", true, None::<&str>)?;
+    let emergency_stop = MenuItem::with_id(app, EMERGENCY_STOP_ITEM, "Emergency stop", true, None::<&str>)?;
+    let stop_agent = MenuItem::with_id(app, STOP_AGENT_ITEM, "Stop agent", false, None::<&str>)?;
+    let show_window = MenuItem::with_id(app, SHOW_WINDOW_ITEM, "Open Skygen", true, None::<&str>)?;
+    let quit = MenuItem::with_id(app, QUIT_ITEM, "Quit", true, None::<&str>)?;
+
+    let profiles = ProfileId::ALL
+        .iter()
+        .map(|&id| {
+            let item_id = format!("{}{}", PROFILE_ITEM_PREFIX, id.name());
+            CheckMenuItem::with_id(app, item_id, id.title(), true, false, None::<&str>).map(|item| (id, item))
+        })
+        .collect::<tauri::Result<Vec<_>>>()?;
+    let profile_items: Vec<&dyn IsMenuItem> = profiles.iter().map(|(_, item)| item as &dyn IsMenuItem).collect();
+    let profile_menu = Submenu::with_items(app, "Backend profile", true, &profile_items)?;
+
+    let menu = Menu::with_items(
+        app,
+        &[
+            &status,
+            &task,
+            &PredefinedMenuItem::separator(app)?,
+            &connect,
+            &stop_agent,
+            &emergency_stop,
+            &toggle_overlay,
+            &PredefinedMenuItem::separator(app)?,
+            &profile_menu,
+            &PredefinedMenuItem::separator(app)?,
+            &show_window,
+            &quit,
+        ],
+    )?;
+
+    let base_icon = app.default_window_icon().cloned().map(Image::to_owned);
+    let mut builder = TrayIconBuilder::new().tooltip("Skygen").menu(&menu).on_menu_event(on_menu_event);
+    if let Some(icon) = &base_icon {
+        builder = builder.icon(icon.clone());
+    }
+    let icon = builder.build(app)?;
+
+    app.manage(Tray { icon, base_icon, status, task, connect, stop_agent, profiles });
+
+    // Вызовы меню синхронно ждут главный поток, поэтому обновление идёт в фоне, а не в обработчике события
+    for event in [STATE_EVENT, CONNECTION_EVENT, TASK_EVENT, settings::CHANGED_EVENT, "auth://expired"] {
+        let handle = app.clone();
+        app.listen_any(event, move |_| refresh(&handle));
+    }
+    refresh(app);
+    Ok(())
+}
+
+/// Перечитывает статус и перерисовывает иконку и меню; вызывается и после входа/регистрации
+pub fn refresh(app: &AppHandle) {
+    tauri::async_runtime::spawn(update(app.clone()));
+}
+
+async fn update(app: AppHandle) {
+    let Some(tray) = app.try_state::<Tray>() else { return };
+    let (authenticated, enrolled) = {
+        let client = app.state::<SkygenState>();
+        let client = client.lock().await;
+        (client.is_authenticated(), client.device().is_some())
+    };
+    let agent = app.state::<Agent>();
+    let running = agent.state().is_active();
+    let status = match agent.connection_state() {
+        _ if !authenticated => Status::SignedOut,
+        _ if !enrolled => Status::NotEnrolled,
+        ConnectionState::Connected => Status::Connected,
+        ConnectionState::Connecting | ConnectionState::Backoff { .. } => Status::Connecting,
+        ConnectionState::Offline { .. } => Status::Disconnected,
+    };
+    let task = agent.current_task();
+    let profile = app.state::<SettingsStore>().get().backend.profile;
+
+    let _ = tray.status.set_text(format!("{} · {}", status.label(), profile.title()));
+    let _ = tray.task.set_text(match &task {
+        Some(task_id) => format!("Task {}", task_id),
+        None => "No task".to_string(),
+    });
+    let _ = tray.connect.set_text(if running { "Disconnect" } else { "Connect" });
+    let _ = tray.connect.set_enabled(running || (authenticated && enrolled));
+    let _ = tray.stop_agent.set_enabled(running);
+    for (id, item) in &tray.profiles {
+        let _ = item.set_checked(*id == profile);
+    }
+    let _ = tray.icon.set_tooltip(Some(format!("Skygen: {}", status.label())));
+    if let Some(base) = &tray.base_icon {
+        let _ = tray.icon.set_icon(Some(with_badge(base, status.badge())));
+    }
+}
+
+/// Иконка приложения с цветной точкой статуса в правом нижнем углу
+fn with_badge(base: &Image<'_>, color: [u8; 3]) -> Image<'static> {
+    let (width, height) = (base.width(), base.height());
+    let mut rgba = base.rgba().to_vec();
+    let radius = f64::from(width.min(height)) / 5.0;
+    let (cx, cy) = (f64::from(width) - radius - 1.0, f64::from(height) - radius - 1.0);
+    for y in 0..height {
+        for x in 0..width {
+            let (dx, dy) = (f64::from(x) + 0.5 - cx, f64::from(y) + 0.5 - cy);
+            if dx * dx + dy * dy <= radius * radius {
+                let offset = ((y * width + x) * 4) as usize;
+                rgba[offset..offset + 4].copy_from_slice(&[color[0], color[1], color[2], 0xFF]);
+            }
+        }
+    }
+    Image::new_owned(rgba, width, height)
+}
+
+/// Обработчик идёт на главном потоке, поэтому всё, что трогает окна или ждёт блокировок, уходит в пул
+fn on_menu_event(app: &AppHandle, event: MenuEvent) {
+    let id = event.id().as_ref();
+    if let Some(name) = id.strip_prefix(PROFILE_ITEM_PREFIX) {
+        if let Some(&profile) = ProfileId::ALL.iter().find(|p| p.name() == name) {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = crate::select_profile(&app, profile).await {
+                    log::warn!("failed to switch to profile {}: {}", profile.name(), e);
+                }
+                // Неудачное переключение снимает галочку, которую меню уже поставило само
+                refresh(&app);
+            });
+        }
+        return;
+    }
+
+    match id {
+        CONNECT_ITEM => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                let agent = app.state::<Agent>();
+                if agent.state().is_active() {
+                    agent.stop(&app);
+                } else if let Err(e) = crate::start_agent(&app, &app.state::<SkygenState>(), &agent).await {
+                    log::warn!("failed to start agent from tray: {}", e);
+                }
+            });
+        }
+        STOP_AGENT_ITEM => app.state::<Agent>().stop(app),
+        EMERGENCY_STOP_ITEM => crate::emergency_stop(app),
+        TOGGLE_OVERLAY_ITEM => {
+            let app = app.clone();
+            tauri::async_runtime::spawn_blocking(move || {
+                if let Err(e) = app.state::<OverlayState>().lock().unwrap().toggle(&app) {
+                    log::warn!("failed to toggle overlay: {}", e);
+                }
+            });
+        }
+        SHOW_WINDOW_ITEM => {
+            // В фоновом режиме окна ещё нет, а создавать его из обработчика меню нельзя: сборка ждёт главный поток
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = crate::open_main_window(&app) {
+                    log::warn!("failed to open main window: {}", e);
+                }
+            });
+        }
+        QUIT_ITEM => crate::quit(app),
+        _ => {}
+    }
+}
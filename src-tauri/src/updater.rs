@@ -0,0 +1,331 @@
+// Обновления приложения: подписанный манифест канала, загрузка в фоне, установка при перезапуске
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+use tauri_plugin_updater::{Update, UpdaterExt};
+
+use crate::error::AppError;
+use crate::settings::{SettingsStore, UpdateSettings};
+
+pub const STATUS_EVENT: &str = "updater://status";
+
+/// Публичный ключ minisign для проверки подписи; задаётся при сборке вместе с ключом подписи релизов.
+/// В отладочной сборке ключ и фид можно подменить одноимёнными переменными окружения при запуске
+const PUBLIC_KEY: Option<&str> = option_env!("SKYGEN_UPDATER_PUBKEY");
+const CHECK_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+const STARTUP_DELAY: Duration = Duration::from_secs(30);
+// Прогресс загрузки отправляется в UI не чаще, чем раз в столько байт
+const PROGRESS_STEP: u64 = 512 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    Beta,
+}
+
+impl UpdateChannel {
+    pub fn name(self) -> &'static str {
+        match self {
+            UpdateChannel::Stable => "stable",
+            UpdateChannel::Beta => "beta",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum UpdateStatus {
+    Idle,
+    Checking,
+    UpToDate,
+    Downloading { version: String, downloaded: u64, total: Option<u64> },
+    /// Загружено и проверено, ставится при перезапуске
+    Ready {
+        version: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        notes: Option<String>,
+    },
+    Failed { error: String },
+}
+
+struct Pending {
+    update: Update,
+    bytes: Vec<u8>,
+}
+
+pub struct Updater {
+    status: Mutex<UpdateStatus>,
+    pending: Mutex<Option<Pending>>,
+    /// Растёт при каждом discard: проверка, начатая раньше, свой результат уже не публикует
+    generation: AtomicU64,
+}
+
+impl Default for Updater {
+    fn default() -> Self {
+        Self { status: Mutex::new(UpdateStatus::Idle), pending: Mutex::new(None), generation: AtomicU64::new(0) }
+    }
+}
+
+impl Updater {
+    pub fn status(&self) -> UpdateStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    fn set_status(&self, app: &AppHandle, status: UpdateStatus) {
+        *self.status.lock().unwrap() = status.clone();
+        let _ = app.emit(STATUS_EVENT, status);
+    }
+
+    /// Проверяет канал из настроек и, если есть новая версия, загружает её; повторный вызов во время загрузки ничего не делает
+    pub async fn check(&self, app: &AppHandle) -> Result<UpdateStatus, AppError> {
+        {
+            let mut status = self.status.lock().unwrap();
+            if matches!(*status, UpdateStatus::Checking | UpdateStatus::Downloading { .. } | UpdateStatus::Ready { .. }) {
+                return Ok(status.clone());
+            }
+            *status = UpdateStatus::Checking;
+        }
+        let generation = self.generation.load(Ordering::SeqCst);
+        let _ = app.emit(STATUS_EVENT, UpdateStatus::Checking);
+        match self.fetch(app, generation).await {
+            Ok(pending) => {
+                let status = match &pending {
+                    Some(pending) => UpdateStatus::Ready { version: pending.update.version.clone(), notes: pending.update.body.clone() },
+                    None => UpdateStatus::UpToDate,
+                };
+                if !self.publish(app, generation, status.clone(), pending) {
+                    return Ok(self.status());
+                }
+                Ok(status)
+            }
+            Err(e) => {
+                log::warn!("update check failed: {}", e);
+                if !self.publish(app, generation, UpdateStatus::Failed { error: e.to_string() }, None) {
+                    return Ok(self.status());
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Записывает итог проверки, если с её начала не было discard; false — результат устарел и отброшен
+    fn publish(&self, app: &AppHandle, generation: u64, status: UpdateStatus, update: Option<Pending>) -> bool {
+        let mut pending = self.pending.lock().unwrap();
+        if self.generation.load(Ordering::SeqCst) != generation {
+            log::info!("dropping update check result for a previous feed");
+            return false;
+        }
+        if update.is_some() {
+            *pending = update;
+        }
+        self.set_status(app, status);
+        true
+    }
+
+    async fn fetch(&self, app: &AppHandle, generation: u64) -> Result<Option<Pending>, AppError> {
+        let Some(update) = find(app).await? else { return Ok(None) };
+        let version = update.version.clone();
+        log::info!("update {} found, downloading", version);
+
+        let (mut downloaded, mut reported) = (0u64, 0u64);
+        // Подпись проверяется внутри download; неподписанный или подменённый пакет сюда не попадёт
+        let bytes = update
+            .download(
+                |chunk, total| {
+                    downloaded += chunk as u64;
+                    if downloaded - reported >= PROGRESS_STEP || total == Some(downloaded) {
+                        reported = downloaded;
+                        let status = UpdateStatus::Downloading { version: version.clone(), downloaded, total };
+                        self.publish(app, generation, status, None);
+                    }
+                },
+                || {},
+            )
+            .await
+            .map_err(|e| AppError::Updater(e.to_string()))?;
+
+        Ok(Some(Pending { update, bytes }))
+    }
+
+    /// Забывает найденное обновление, например после смены канала; проверка в работе тоже не сохранит своё
+    pub fn discard(&self, app: &AppHandle) {
+        let mut pending = self.pending.lock().unwrap();
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        if pending.take().is_some() || self.status() != UpdateStatus::Idle {
+            self.set_status(app, UpdateStatus::Idle);
+        }
+    }
+
+    /// Ставит загруженное обновление; false, если ставить нечего
+    pub fn install(&self) -> Result<bool, AppError> {
+        let Some(pending) = self.pending.lock().unwrap().take() else { return Ok(false) };
+        log::info!("installing update {}", pending.update.version);
+        pending.update.install(&pending.bytes).map_err(|e| AppError::Updater(e.to_string()))?;
+        Ok(true)
+    }
+}
+
+fn public_key() -> Option<String> {
+    #[cfg(debug_assertions)]
+    if let Ok(key) = std::env::var("SKYGEN_UPDATER_PUBKEY") {
+        return Some(key);
+    }
+    PUBLIC_KEY.map(str::to_string)
+}
+
+fn endpoint(updates: UpdateSettings) -> Option<String> {
+    #[cfg(debug_assertions)]
+    if let Ok(feed_url) = std::env::var("SKYGEN_UPDATE_FEED") {
+        return UpdateSettings { feed_url, ..updates }.endpoint();
+    }
+    updates.endpoint()
+}
+
+async fn find(app: &AppHandle) -> Result<Option<Update>, AppError> {
+    let public_key = public_key().ok_or_else(|| AppError::Updater("this build has no updater public key".into()))?;
+    let endpoint = endpoint(app.state::<SettingsStore>().get().updates)
+        .ok_or_else(|| AppError::Updater("update feed is not configured".into()))?;
+    check_feed(app, &public_key, &endpoint).await
+}
+
+async fn check_feed<R: Runtime>(app: &AppHandle<R>, public_key: &str, endpoint: &str) -> Result<Option<Update>, AppError> {
+    let endpoint = reqwest::Url::parse(endpoint).map_err(|e| AppError::Updater(e.to_string()))?;
+    app.updater_builder()
+        .pubkey(public_key)
+        .endpoints(vec![endpoint])
+        .and_then(|builder| builder.build())
+        .map_err(|e| AppError::Updater(e.to_string()))?
+        .check()
+        .await
+        .map_err(|e| AppError::Updater(e.to_string()))
+}
+
+/// Фоновая проверка: через полминуты после старта и дальше раз в несколько часов, если она включена в настройках
+pub fn spawn(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(STARTUP_DELAY).await;
+        loop {
+            if app.state::<SettingsStore>().get().updates.auto_check {
+                let _ = app.state::<Updater>().check(&app).await;
+            }
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::Engine;
+    use std::io::{Read, Write};
+
+    // Ключ и подпись содержимого b"test" из тестов minisign-verify (формат Ed, без предварительного хеша)
+    const TEST_PUBLIC_KEY: &str =
+        "untrusted comment: minisign public key E7620F1842B4E81F\nRWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3";
+    const TEST_SIGNATURE: &str = "untrusted comment: signature from minisign secret key
+RWQf6LRCGA9i59SLOFxz6NxvASXDJeRtuZykwQepbDEGt87ig1BNpWaVWuNrm73YiIiJbq71Wi+dP9eKL8OC351vwIasSSbXxwA=
+trusted comment: timestamp:1555779966\tfile:test
+QtKMXWyYcwdpZAlPF7tE2ENJkRd1ujvKjlj1m9RtHTBnZPa5WKU5uWRs5GoP5M/VqE81QFuMKI5k/SfNQUaOAA==";
+
+    fn base64(text: &str) -> String {
+        base64::engine::general_purpose::STANDARD.encode(text)
+    }
+
+    /// Фид на локальном HTTP-сервере: stable/latest.json с подписанным пакетом и сам пакет
+    fn serve(package: &'static [u8]) -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let feed = format!("http://{}", listener.local_addr().unwrap());
+        let mut platforms = serde_json::Map::new();
+        platforms.insert(
+            tauri_plugin_updater::target().unwrap(),
+            serde_json::json!({ "url": format!("{}/skygen.tar.gz", feed), "signature": base64(TEST_SIGNATURE) }),
+        );
+        let manifest = serde_json::json!({ "version": "99.0.0", "notes": "test release", "platforms": platforms }).to_string();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { continue };
+                let mut request = [0u8; 4096];
+                let read = stream.read(&mut request).unwrap_or(0);
+                let request = String::from_utf8_lossy(&request[..read]);
+                let body = match request.split_whitespace().nth(1) {
+                    Some("/stable/latest.json") => manifest.as_bytes(),
+                    _ => package,
+                };
+                let _ = write!(stream, "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", body.len());
+                let _ = stream.write_all(body);
+            }
+        });
+        feed
+    }
+
+    fn mock_app() -> tauri::App<tauri::test::MockRuntime> {
+        let mut context = tauri::test::mock_context(tauri::test::noop_assets());
+        context.config_mut().plugins.0.insert("updater".into(), serde_json::json!({ "pubkey": "" }));
+        tauri::test::mock_builder().plugin(tauri_plugin_updater::Builder::new().build()).build(context).unwrap()
+    }
+
+    fn check(feed_url: &str) -> Update {
+        let app = mock_app();
+        let endpoint = feed(feed_url, UpdateChannel::Stable).endpoint().unwrap();
+        tauri::async_runtime::block_on(check_feed(app.handle(), &base64(TEST_PUBLIC_KEY), &endpoint))
+            .unwrap()
+            .expect("the feed announces a newer version")
+    }
+
+    #[test]
+    fn signed_package_from_feed_is_downloaded() {
+        let update = check(&serve(b"test"));
+        assert_eq!(update.version, "99.0.0");
+        assert_eq!(update.body.as_deref(), Some("test release"));
+        let bytes = tauri::async_runtime::block_on(update.download(|_, _| {}, || {})).unwrap();
+        assert_eq!(bytes, b"test");
+    }
+
+    #[test]
+    fn package_not_matching_signature_is_rejected() {
+        let update = check(&serve(b"Test"));
+        assert!(tauri::async_runtime::block_on(update.download(|_, _| {}, || {})).is_err());
+    }
+
+    fn feed(url: &str, channel: UpdateChannel) -> UpdateSettings {
+        UpdateSettings { channel, auto_check: true, feed_url: url.into() }
+    }
+
+    #[test]
+    fn each_channel_has_its_own_manifest() {
+        let stable = feed("http://127.0.0.1:8000", UpdateChannel::Stable);
+        assert_eq!(stable.endpoint().as_deref(), Some("http://127.0.0.1:8000/stable/latest.json"));
+        let beta = feed("http://127.0.0.1:8000/", UpdateChannel::Beta);
+        assert_eq!(beta.endpoint().as_deref(), Some("http://127.0.0.1:8000/beta/latest.json"));
+    }
+
+    #[test]
+    fn empty_feed_disables_updates() {
+        assert_eq!(feed("", UpdateChannel::Stable).endpoint(), None);
+        assert_eq!(feed("", UpdateChannel::Beta).endpoint(), None);
+    }
+
+    #[test]
+    fn switching_channel_changes_endpoint() {
+        // По смене endpoint save_settings сбрасывает уже найденное обновление
+        let stable = feed("https://updates.example.com", UpdateChannel::Stable);
+        let beta = UpdateSettings { channel: UpdateChannel::Beta, ..stable.clone() };
+        assert_ne!(stable.endpoint(), beta.endpoint());
+        assert_eq!(stable.endpoint(), UpdateSettings { auto_check: false, ..stable.clone() }.endpoint());
+    }
+
+    #[test]
+    fn channel_and_status_match_frontend_types() {
+        assert_eq!(serde_json::to_value(UpdateChannel::Beta).unwrap(), "beta");
+        assert_eq!(serde_json::from_str::<UpdateChannel>("\"stable\"").unwrap(), UpdateChannel::Stable);
+        assert_eq!(serde_json::to_value(UpdateStatus::UpToDate).unwrap(), serde_json::json!({ "state": "up_to_date" }));
+        assert_eq!(
+            serde_json::to_value(UpdateStatus::Ready { version: "1.2.0".into(), notes: None }).unwrap(),
+            serde_json::json!({ "state": "ready", "version": "1.2.0" })
+        );
+    }
+}
@@ -0,0 +1,67 @@
+"use client";
+
+import { useEffect, useState } from "react";
+
+type OutlineAnimation = "none" | "pulse" | "flash";
+
+interface OutlineStyle {
+  color: string;
+  width: number;
+  blur: number;
+  animation: OutlineAnimation;
+}
+
+const DEFAULT_STYLE: OutlineStyle = { color: "#FF4D4F", width: 4, blur: 0, animation: "none" };
+
+const ANIMATIONS: Record<OutlineAnimation, string | undefined> = {
+  none: undefined,
+  pulse: "outline-pulse 1.6s ease-in-out infinite",
+  flash: "outline-flash 0.3s steps(1) 4",
+};
+
+// Начальный стиль приходит в query string, дальнейшие изменения — событием outline://style
+function styleFromQuery(): OutlineStyle {
+  const params = new URLSearchParams(window.location.search);
+  const color = params.get("color");
+  return {
+    color: color ? (color.startsWith("#") ? color : `#${color}`) : DEFAULT_STYLE.color,
+    width: Number(params.get("width") ?? DEFAULT_STYLE.width),
+    blur: Number(params.get("blur") ?? DEFAULT_STYLE.blur),
+    animation: (params.get("animation") as OutlineAnimation | null) ?? DEFAULT_STYLE.animation,
+  };
+}
+
+export default function OutlinePage() {
+  const [style, setStyle] = useState<OutlineStyle | null>(null);
+
+  useEffect(() => {
+    setStyle(styleFromQuery());
+    let unlisten: (() => void) | undefined;
+    import("@tauri-apps/api/event").then(({ listen }) =>
+      listen<OutlineStyle>("outline://style", (event) => setStyle(event.payload))
+    ).then((fn) => { unlisten = fn; });
+    return () => unlisten?.();
+  }, []);
+
+  if (!style) return null;
+
+  return (
+    <>
+      <div
+        // key перезапускает анимацию, когда приходит новый стиль (например, повторная вспышка)
+        key={`${style.animation}-${style.color}`}
+        className="fixed inset-0 pointer-events-none"
+        style={{
+          animation: ANIMATIONS[style.animation],
+          border: `${style.width}px solid ${style.color}`,
+          boxShadow: style.blur > 0 ? `inset 0 0 ${style.blur}px ${style.color}` : undefined,
+        }}
+      />
+      <style jsx global>{`
+        html, body, #__next { background: transparent !important; overflow: hidden; }
+        @keyframes outline-pulse { 0%, 100% { opacity: 1; } 50% { opacity: 0.35; } }
+        @keyframes outline-flash { 0% { opacity: 1; } 50% { opacity: 0; } }
+      `}</style>
+    </>
+  );
+}
@@ -1,6 +1,8 @@
 "use client";
 
-import { useEffect } from "react";
+import { useEffect, useRef, useState } from "react";
+import { listen } from "@tauri-apps/api/event";
+import { SkygenService, type Approval, type ApprovalRequest } from "@/services/skygenService";
 
 declare global {
   interface Window {
@@ -14,19 +16,59 @@ declare global {
   }
 }
 
+const hideOverlay = () => {
+  if (window.webkit?.messageHandlers?.overlay) {
+    window.webkit.messageHandlers.overlay.postMessage({ action: 'hideOverlay' });
+  } else {
+    import("@tauri-apps/api/core").then(({ invoke }) => invoke("hide_overlay"));
+  }
+};
+
 export default function OverlayPage() {
+  const [approvals, setApprovals] = useState<ApprovalRequest[]>([]);
+  // Окно создаётся скрытым при старте; каждый показ перезапускает анимацию появления через key
+  const [shownAt, setShownAt] = useState(0);
+  const inputRef = useRef<HTMLInputElement>(null);
+  const approval = approvals[0];
+
+  useEffect(() => {
+    const unlisten = listen<boolean>("overlay://visibility", (event) => {
+      if (!event.payload) return;
+      setShownAt(Date.now());
+      SkygenService.getInstance().getPendingApprovals().then(setApprovals).catch(() => {});
+      inputRef.current?.focus();
+    });
+    return () => { unlisten.then((fn) => fn()); };
+  }, []);
+
+  // Запросы подтверждения от агента: окно могло открыться уже после события, поэтому забираем и очередь
+  useEffect(() => {
+    const service = SkygenService.getInstance();
+    service.getPendingApprovals().then(setApprovals).catch(() => {});
+    const unlisten = service.onApprovalRequest((request) => {
+      setApprovals((current) => current.some((r) => r.id === request.id) ? current : [...current, request]);
+    });
+    return () => { unlisten.then((fn) => fn()); };
+  }, []);
+
+  const respond = async (decision: Approval) => {
+    if (!approval) return;
+    try {
+      await SkygenService.getInstance().respondApproval(approval.id, decision);
+    } catch (error) {
+      console.error("Approval response failed:", error);
+    }
+    const rest = approvals.slice(1);
+    setApprovals(rest);
+    if (rest.length === 0) hideOverlay();
+  };
+
   // Close on Escape
   useEffect(() => {
     const handleEscape = (e: KeyboardEvent) => {
       if (e.key === "Escape") {
         console.log("🔑 ESC pressed, hiding overlay");
-        // Try Swift bridge first
-        if (window.webkit?.messageHandlers?.overlay) {
-          window.webkit.messageHandlers.overlay.postMessage({ action: 'hideOverlay' });
-        } else {
-          // Fallback to Tauri
-          import("@tauri-apps/api/core").then(({ invoke }) => invoke("hide_overlay"));
-        }
+        hideOverlay();
       }
     };
 
@@ -39,11 +81,31 @@ export default function OverlayPage() {
   return (
     <div className="w-full h-full flex items-center justify-center bg-transparent">
       <div
+        key={shownAt}
         className="relative w-[680px] max-w-[90vw] rounded-2xl backdrop-blur-2xl bg-white/60 dark:bg-white/10 shadow-2xl ring-1 ring-black/10 dark:ring-white/10 animate-fadeIn"
         style={{
           WebkitBackdropFilter: "saturate(180%) blur(20px)",
         }}
       >
+        {approval ? (
+          <div className="px-5 py-3">
+            <div className="text-[13px] font-medium text-black/80 dark:text-white/80">
+              Remote task wants to {approval.actions.length === 1 ? "run an action" : `run ${approval.actions.length} actions`}
+            </div>
+            <div className="mt-1 truncate text-[13px] text-black/60 dark:text-white/60" title={approval.actions.map((a) => a.summary).join("\n")}>
+              {approval.actions.map((a) => a.summary).join(" · ")}
+            </div>
+            <div className="mt-2 flex gap-2 text-[12px]">
+              <button onClick={() => respond("allow_once")} className="rounded-md px-3 py-1 bg-black/80 text-white dark:bg-white/90 dark:text-black">Allow</button>
+              <button onClick={() => respond("allow_session")} className="rounded-md px-3 py-1 ring-1 ring-black/10 dark:ring-white/10 text-black/70 dark:text-white/70">Allow all this session</button>
+              <button onClick={() => respond("always_allow")} className="rounded-md px-3 py-1 ring-1 ring-black/10 dark:ring-white/10 text-black/70 dark:text-white/70">Always allow</button>
+              <div className="flex-1" />
+              <button onClick={() => respond("deny")} className="rounded-md px-3 py-1 ring-1 ring-red-500/40 text-red-600 dark:text-red-400">Deny</button>
+              <button onClick={() => respond("always_deny")} className="rounded-md px-3 py-1 ring-1 ring-red-500/40 text-red-600 dark:text-red-400">Always deny</button>
+            </div>
+          </div>
+        ) : (
+        <>
         <div className="px-5 py-4">
           <div className="flex items-center gap-3">
             <div className="shrink-0 h-7 w-7 rounded-full bg-black/10 dark:bg-white/15 flex items-center justify-center ring-1 ring-black/10 dark:ring-white/10">
@@ -55,6 +117,7 @@ export default function OverlayPage() {
               </svg>
             </div>
             <input
+              ref={inputRef}
               autoFocus
               className="flex-1 bg-transparent placeholder-black/50 dark:placeholder-white/40 text-black dark:text-white text-[17px] leading-7 outline-none"
               placeholder="Enter command or query"
@@ -72,6 +135,8 @@ export default function OverlayPage() {
             <div className="rounded-lg bg-black/5 dark:bg-white/5 ring-1 ring-black/10 dark:ring-white/10 px-3 py-2 text-black/70 dark:text-white/70">Create task</div>
           </div>
         </div>
+        </>
+        )}
       </div>
       <style jsx global>{`
         html, body, #__next { background: transparent !important; }
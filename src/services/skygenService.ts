@@ -1,4 +1,5 @@
 import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
 
 export interface AuthResponse {
     success: boolean;
@@ -6,6 +7,41 @@ export interface AuthResponse {
     error?: string;
 }
 
+/**
+ * Ошибка, которую возвращают все Tauri-команды (AppError в Rust)
+ */
+export interface AppErrorPayload {
+    code: string;
+    message: string;
+    details?: string | null;
+}
+
+export class SkygenError extends Error {
+    readonly code: string;
+    readonly details?: string | null;
+
+    constructor(payload: AppErrorPayload) {
+        super(payload.message);
+        this.name = 'SkygenError';
+        this.code = payload.code;
+        this.details = payload.details;
+    }
+}
+
+function isAppErrorPayload(error: unknown): error is AppErrorPayload {
+    return typeof error === 'object' && error !== null && 'code' in error && 'message' in error;
+}
+
+function toSkygenError(error: unknown, fallback: string): Error {
+    if (error instanceof Error) {
+        return error;
+    }
+    if (isAppErrorPayload(error)) {
+        return new SkygenError(error);
+    }
+    return new Error(`${fallback}: ${error}`);
+}
+
 export interface StatusResponse {
     authenticated: boolean;
     device_enrolled: boolean;
@@ -13,6 +49,112 @@ export interface StatusResponse {
     device_id?: string;
     platform: string;
     desktop_env_available: boolean;
+    action_backends: string[];
+}
+
+export type AgentState =
+    | { state: 'stopped' | 'starting' | 'running' | 'stopping' }
+    | { state: 'reconnecting'; attempt: number }
+    | { state: 'failed'; error: string };
+
+export type ConnectionState =
+    | { state: 'connecting' | 'connected' }
+    | { state: 'backoff'; attempt: number; delay_ms: number }
+    | { state: 'offline'; reason?: string };
+
+export type ActionKind =
+    | 'screenshot' | 'accessibility' | 'type_text' | 'key_press'
+    | 'mouse_move' | 'click' | 'scroll' | 'execute_action';
+
+export type ActionClass = 'read_only' | 'input' | 'filesystem';
+
+export type Approval = 'allow_once' | 'allow_session' | 'always_allow' | 'deny' | 'always_deny';
+
+export interface ApprovalRequest {
+    id: string;
+    task_id: string;
+    actions: { action_type: ActionKind; class: ActionClass; summary: string }[];
+}
+
+export interface PolicyRule {
+    action: ActionKind;
+    decision: 'allow' | 'deny';
+}
+
+export interface AuditEvent {
+    seq: number;
+    timestamp: string;
+    kind: 'task_received' | 'action' | 'task_finished' | 'segment_started';
+    task_id: string;
+    action_id?: string;
+    action_type?: string;
+    params?: unknown;
+    status?: 'done' | 'failed' | 'denied' | 'cancelled' | 'completed';
+    error?: string;
+    duration_ms?: number;
+    prev_hash: string;
+    hash: string;
+}
+
+export interface AuditVerification {
+    valid: boolean;
+    events: number;
+    broken_at?: number;
+}
+
+export type OutlineMode = 'off' | 'manual' | 'working' | 'awaiting_approval' | 'error' | 'alert';
+
+export type OverlayTarget =
+    | { mode: 'auto' | 'primary' }
+    | { mode: 'monitor'; name: string };
+
+export interface MonitorInfo {
+    index: number;
+    name?: string | null;
+    x: number;
+    y: number;
+    width: number;
+    height: number;
+    scale_factor: number;
+    primary: boolean;
+}
+
+export type LogLevel = 'error' | 'warn' | 'info' | 'debug' | 'trace';
+
+export type ProfileId = 'local' | 'staging' | 'production' | 'custom';
+
+export interface BackendProfile {
+    url: string;
+    wss_url?: string | null;
+}
+
+export interface Settings {
+    backend: { profile: ProfileId; profiles: Partial<Record<ProfileId, BackendProfile>> };
+    outline: { color: string; width: number; blur: number };
+    overlay: { width: number; height: number; target: OverlayTarget };
+    log_level: LogLevel;
+    autostart: boolean;
+    updates: { channel: UpdateChannel; auto_check: boolean; feed_url: string };
+    shortcuts: Partial<Record<ShortcutAction, string>>;
+}
+
+export type UpdateChannel = 'stable' | 'beta';
+
+export type UpdateStatus =
+    | { state: 'idle' | 'checking' | 'up_to_date' }
+    | { state: 'downloading'; version: string; downloaded: number; total?: number | null }
+    | { state: 'ready'; version: string; notes?: string }
+    | { state: 'failed'; error: string };
+
+export type DeepLinkAuthOutcome = { status: 'signed_in' } | { status: 'failed'; error: AppErrorPayload };
+
+export type ShortcutAction = 'toggle_overlay' | 'cancel_task' | 'push_to_talk' | 'toggle_outline';
+
+export interface ShortcutInfo {
+    action: ShortcutAction;
+    accelerator?: string | null;
+    registered: boolean;
+    error?: string;
 }
 
 export interface LoginData {
@@ -47,7 +189,7 @@ export class SkygenService {
             const result = await invoke<string>('install_dependencies');
             return result;
         } catch (error) {
-            throw new Error(`Failed to install dependencies: ${error}`);
+            throw toSkygenError(error, 'Failed to install dependencies');
         }
     }
 
@@ -59,7 +201,7 @@ export class SkygenService {
             const response = await invoke<StatusResponse>('skygen_get_status');
             return response;
         } catch (error) {
-            throw new Error(`Failed to get status: ${error}`);
+            throw toSkygenError(error, 'Failed to get status');
         }
     }
 
@@ -79,10 +221,7 @@ export class SkygenService {
 
             return response.data;
         } catch (error) {
-            if (error instanceof Error) {
-                throw error;
-            }
-            throw new Error(`Signup failed: ${error}`);
+            throw toSkygenError(error, 'Signup failed');
         }
     }
 
@@ -100,18 +239,13 @@ export class SkygenService {
                 throw new Error(response.error || 'Login failed');
             }
 
+            // Токены хранит Rust-сторона (keyring или зашифрованный файл), в localStorage их не кладём
             const loginData = response.data as LoginData;
-
-            // Сохраняем токены в localStorage
-            localStorage.setItem('access_token', loginData.access_token);
-            localStorage.setItem('refresh_token', loginData.refresh_token);
+            localStorage.setItem('authenticated', 'true');
 
             return loginData;
         } catch (error) {
-            if (error instanceof Error) {
-                throw error;
-            }
-            throw new Error(`Login failed: ${error}`);
+            throw toSkygenError(error, 'Login failed');
         }
     }
 
@@ -128,17 +262,12 @@ export class SkygenService {
 
             const deviceData = response.data as DeviceData;
 
-            // Сохраняем данные устройства
+            // Сохраняем только публичные данные устройства, device_token остаётся в хранилище
             localStorage.setItem('device_id', deviceData.device_id);
-            localStorage.setItem('device_token', deviceData.device_token);
-            localStorage.setItem('wss_url', deviceData.wss_url);
 
             return deviceData;
         } catch (error) {
-            if (error instanceof Error) {
-                throw error;
-            }
-            throw new Error(`Device enrollment failed: ${error}`);
+            throw toSkygenError(error, 'Device enrollment failed');
         }
     }
 
@@ -155,18 +284,382 @@ export class SkygenService {
 
             return response.data?.connected || false;
         } catch (error) {
-            if (error instanceof Error) {
-                throw error;
-            }
-            throw new Error(`Connection failed: ${error}`);
+            throw toSkygenError(error, 'Connection failed');
+        }
+    }
+
+    /**
+     * Остановка фонового агента
+     */
+    async disconnect(): Promise<AgentState> {
+        return invoke<AgentState>('agent_stop');
+    }
+
+    /**
+     * Аварийная остановка: отменяет текущую задачу и очередь, соединение остаётся
+     */
+    async emergencyStop(): Promise<void> {
+        await invoke('agent_emergency_stop');
+    }
+
+    /**
+     * Подписка на аварийную остановку (хоткей, трей или UI)
+     */
+    onEmergencyStop(callback: () => void): Promise<UnlistenFn> {
+        return listen('agent://emergency-stop', () => callback());
+    }
+
+    /**
+     * Текущее состояние фонового агента
+     */
+    async getAgentState(): Promise<AgentState> {
+        return invoke<AgentState>('agent_status');
+    }
+
+    /**
+     * Подписка на смену состояния агента
+     */
+    onAgentState(callback: (state: AgentState) => void): Promise<UnlistenFn> {
+        return listen<AgentState>('agent://state', (event) => callback(event.payload));
+    }
+
+    /**
+     * Подписка на состояние WebSocket-соединения (connecting, connected, backoff, offline)
+     */
+    onConnectionState(callback: (state: ConnectionState) => void): Promise<UnlistenFn> {
+        return listen<ConnectionState>('agent://connection', (event) => callback(event.payload));
+    }
+
+    /**
+     * Идентификатор выполняемой задачи; null, когда агент свободен
+     */
+    onAgentTask(callback: (taskId: string | null) => void): Promise<UnlistenFn> {
+        return listen<string | null>('agent://task', (event) => callback(event.payload));
+    }
+
+    /**
+     * Подписка на истечение сессии (refresh token больше не принимается)
+     */
+    onSessionExpired(callback: () => void): Promise<UnlistenFn> {
+        return listen('auth://expired', () => {
+            localStorage.removeItem('authenticated');
+            callback();
+        });
+    }
+
+    /**
+     * Запросы подтверждения, которые ещё ждут ответа
+     */
+    async getPendingApprovals(): Promise<ApprovalRequest[]> {
+        return invoke<ApprovalRequest[]>('pending_approvals');
+    }
+
+    /**
+     * Подписка на новые запросы подтверждения удалённых действий
+     */
+    onApprovalRequest(callback: (request: ApprovalRequest) => void): Promise<UnlistenFn> {
+        return listen<ApprovalRequest>('approval://request', (event) => callback(event.payload));
+    }
+
+    /**
+     * Ответ на запрос подтверждения
+     */
+    async respondApproval(id: string, decision: Approval): Promise<void> {
+        try {
+            await invoke('respond_approval', { id, decision });
+        } catch (error) {
+            throw toSkygenError(error, 'Failed to respond to approval request');
         }
     }
 
+    /**
+     * Постоянные правила разрешения и запрета действий
+     */
+    async listPolicyRules(): Promise<PolicyRule[]> {
+        return invoke<PolicyRule[]>('list_policy_rules');
+    }
+
+    /**
+     * Установка или удаление (decision = null) постоянного правила
+     */
+    async setPolicyRule(action: ActionKind, decision: PolicyRule['decision'] | null): Promise<PolicyRule[]> {
+        try {
+            return await invoke<PolicyRule[]>('set_policy_rule', { action, decision });
+        } catch (error) {
+            throw toSkygenError(error, 'Failed to update policy rule');
+        }
+    }
+
+    /**
+     * События журнала аудита, начиная с since (RFC 3339)
+     */
+    async listAuditEvents(since?: string, limit?: number): Promise<AuditEvent[]> {
+        try {
+            return await invoke<AuditEvent[]>('list_audit_events', { since, limit });
+        } catch (error) {
+            throw toSkygenError(error, 'Failed to read audit log');
+        }
+    }
+
+    /**
+     * Экспорт журнала аудита в JSONL-файл, возвращает число записей
+     */
+    async exportAuditLog(destination: string): Promise<number> {
+        try {
+            return await invoke<number>('export_audit_log', { destination });
+        } catch (error) {
+            throw toSkygenError(error, 'Failed to export audit log');
+        }
+    }
+
+    /**
+     * Проверка целостности цепочки хешей журнала
+     */
+    async verifyAuditLog(): Promise<AuditVerification> {
+        try {
+            return await invoke<AuditVerification>('verify_audit_log');
+        } catch (error) {
+            throw toSkygenError(error, 'Failed to verify audit log');
+        }
+    }
+
+    /**
+     * Показ рамки "агент управляет экраном"
+     */
+    async startOutline(color: string, width?: number, blur?: number): Promise<void> {
+        try {
+            await invoke('start_outline', { color, width, blur });
+        } catch (error) {
+            throw toSkygenError(error, 'Failed to start outline');
+        }
+    }
+
+    /**
+     * Изменение стиля запущенной рамки; ошибка, если helper не подтвердил изменение
+     */
+    async updateOutline(style: { color?: string; width?: number; blur?: number }): Promise<void> {
+        try {
+            await invoke('update_outline', style);
+        } catch (error) {
+            throw toSkygenError(error, 'Failed to update outline');
+        }
+    }
+
+    /**
+     * Переключение пресета рамки; агент сам меняет его по ходу задачи, пока режим не manual
+     */
+    async setOutlineMode(mode: OutlineMode): Promise<void> {
+        try {
+            await invoke('set_outline_mode', { mode });
+        } catch (error) {
+            throw toSkygenError(error, 'Failed to set outline mode');
+        }
+    }
+
+    async stopOutline(): Promise<void> {
+        try {
+            await invoke('stop_outline');
+        } catch (error) {
+            throw toSkygenError(error, 'Failed to stop outline');
+        }
+    }
+
+    /**
+     * Подписка на сбои рамки, случившиеся без вызова из UI (helper падает после перезапусков)
+     */
+    onOutlineError(callback: (error: string) => void): Promise<UnlistenFn> {
+        return listen<string>('outline://error', (event) => callback(event.payload));
+    }
+
+    /**
+     * Глобальные хоткеи: все действия, включая неназначенные
+     */
+    async listShortcuts(): Promise<ShortcutInfo[]> {
+        return invoke<ShortcutInfo[]>('list_shortcuts');
+    }
+
+    /**
+     * Назначение сочетания (например, "CommandOrControl+Shift+K"); ошибка shortcut_conflict или shortcut_unavailable, если занято
+     */
+    async registerShortcut(action: ShortcutAction, accelerator: string): Promise<ShortcutInfo[]> {
+        try {
+            return await invoke<ShortcutInfo[]>('register_shortcut', { action, accelerator });
+        } catch (error) {
+            throw toSkygenError(error, 'Failed to register shortcut');
+        }
+    }
+
+    async rebindShortcut(action: ShortcutAction, accelerator: string): Promise<ShortcutInfo[]> {
+        try {
+            return await invoke<ShortcutInfo[]>('rebind_shortcut', { action, accelerator });
+        } catch (error) {
+            throw toSkygenError(error, 'Failed to rebind shortcut');
+        }
+    }
+
+    async unregisterShortcut(action: ShortcutAction): Promise<ShortcutInfo[]> {
+        try {
+            return await invoke<ShortcutInfo[]>('unregister_shortcut', { action });
+        } catch (error) {
+            throw toSkygenError(error, 'Failed to unregister shortcut');
+        }
+    }
+
+    /**
+     * Срабатывание хоткея; pressed = false приходит при отпускании (нужно для push-to-talk)
+     */
+    onShortcut(callback: (action: ShortcutAction, pressed: boolean) => void): Promise<UnlistenFn> {
+        return listen<{ action: ShortcutAction; pressed: boolean }>('shortcut://triggered', (event) =>
+            callback(event.payload.action, event.payload.pressed));
+    }
+
+    /**
+     * Подключённые мониторы (координаты и размеры в физических пикселях)
+     */
+    async listMonitors(): Promise<MonitorInfo[]> {
+        try {
+            return await invoke<MonitorInfo[]>('list_monitors');
+        } catch (error) {
+            throw toSkygenError(error, 'Failed to list monitors');
+        }
+    }
+
+    async getOverlayTarget(): Promise<OverlayTarget> {
+        return invoke<OverlayTarget>('get_overlay_target');
+    }
+
+    /**
+     * Монитор для оверлея: auto — под курсором, либо закреплённый по имени
+     */
+    async setOverlayTarget(target: OverlayTarget): Promise<void> {
+        try {
+            await invoke('set_overlay_target', { target });
+        } catch (error) {
+            throw toSkygenError(error, 'Failed to set overlay target');
+        }
+    }
+
+    async getSettings(): Promise<Settings> {
+        return invoke<Settings>('get_settings');
+    }
+
+    /**
+     * Сохраняет настройки целиком; невалидные отклоняются с кодом invalid_settings
+     */
+    async updateSettings(settings: Settings): Promise<Settings> {
+        try {
+            return await invoke<Settings>('update_settings', { settings });
+        } catch (error) {
+            throw toSkygenError(error, 'Failed to update settings');
+        }
+    }
+
+    /**
+     * Переключает окружение бекенда; агент отключается, сессия и устройство берутся из нового профиля
+     */
+    async setBackendProfile(profile: ProfileId): Promise<Settings> {
+        try {
+            return await invoke<Settings>('set_backend_profile', { profile });
+        } catch (error) {
+            throw toSkygenError(error, 'Failed to switch backend profile');
+        }
+    }
+
+    /**
+     * Меняет уровень журнала сразу, без перезапуска; значение сохраняется в настройках
+     */
+    async setLogLevel(level: LogLevel): Promise<Settings> {
+        try {
+            return await invoke<Settings>('set_log_level', { level });
+        } catch (error) {
+            throw toSkygenError(error, 'Failed to change log level');
+        }
+    }
+
+    /**
+     * Архив для поддержки: журналы, настройки и сведения о системе; возвращает число файлов
+     */
+    async exportLogs(destination: string): Promise<number> {
+        try {
+            return await invoke<number>('export_logs', { destination });
+        } catch (error) {
+            throw toSkygenError(error, 'Failed to export logs');
+        }
+    }
+
+    async getUpdateStatus(): Promise<UpdateStatus> {
+        return invoke<UpdateStatus>('get_update_status');
+    }
+
+    /**
+     * Проверяет канал из настроек; найденное обновление загружается в фоне, прогресс приходит в onUpdateStatus
+     */
+    async checkForUpdates(): Promise<UpdateStatus> {
+        try {
+            return await invoke<UpdateStatus>('check_for_updates');
+        } catch (error) {
+            throw toSkygenError(error, 'Failed to check for updates');
+        }
+    }
+
+    /**
+     * Ставит загруженное обновление и перезапускает приложение
+     */
+    async applyUpdate(): Promise<void> {
+        try {
+            await invoke('apply_update');
+        } catch (error) {
+            throw toSkygenError(error, 'Failed to apply update');
+        }
+    }
+
+    onUpdateStatus(callback: (status: UpdateStatus) => void): Promise<UnlistenFn> {
+        return listen<UpdateStatus>('updater://status', (event) => callback(event.payload));
+    }
+
+    /**
+     * Повторный запуск приложения: аргументы второго процесса (без пути к exe)
+     */
+    onSecondInstance(callback: (args: string[]) => void): Promise<UnlistenFn> {
+        return listen<string[]>('app://second-instance', (event) => callback(event.payload));
+    }
+
+    /**
+     * Вход по ссылке skygen://auth/verify из письма; при успехе приложение уже авторизовано
+     */
+    onDeepLinkAuth(callback: (outcome: DeepLinkAuthOutcome) => void): Promise<UnlistenFn> {
+        return listen<DeepLinkAuthOutcome>('deep-link://auth', (event) => {
+            if (event.payload.status === 'signed_in') {
+                localStorage.setItem('authenticated', 'true');
+            }
+            callback(event.payload);
+        });
+    }
+
+    onReferral(callback: (code: string) => void): Promise<UnlistenFn> {
+        return listen<{ code: string }>('deep-link://referral', (event) => callback(event.payload.code));
+    }
+
+    onDeepLinkTask(callback: (id: string) => void): Promise<UnlistenFn> {
+        return listen<{ id: string }>('deep-link://task', (event) => callback(event.payload.id));
+    }
+
+    /**
+     * Реферальный код из ссылки, пришедшей до подписки на onReferral; возвращается один раз
+     */
+    async takeReferralCode(): Promise<string | null> {
+        return invoke<string | null>('take_referral_code');
+    }
+
+    onSettingsChanged(callback: (settings: Settings) => void): Promise<UnlistenFn> {
+        return listen<Settings>('settings://changed', (event) => callback(event.payload));
+    }
+
     /**
      * Проверка авторизации
      */
     isAuthenticated(): boolean {
-        return !!localStorage.getItem('access_token');
+        return localStorage.getItem('authenticated') === 'true';
     }
 
     /**
@@ -176,13 +669,6 @@ export class SkygenService {
         return !!localStorage.getItem('device_id');
     }
 
-    /**
-     * Получение токена доступа
-     */
-    getAccessToken(): string | null {
-        return localStorage.getItem('access_token');
-    }
-
     /**
      * Получение ID устройства
      */
@@ -194,11 +680,9 @@ export class SkygenService {
      * Выход из системы
      */
     logout(): void {
-        localStorage.removeItem('access_token');
-        localStorage.removeItem('refresh_token');
+        localStorage.removeItem('authenticated');
         localStorage.removeItem('device_id');
-        localStorage.removeItem('device_token');
-        localStorage.removeItem('wss_url');
+        invoke('skygen_logout').catch((error) => console.error('Logout failed:', error));
     }
 
     /**